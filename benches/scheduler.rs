@@ -0,0 +1,59 @@
+extern crate poolite;
+use poolite::{Builder, Pool, Scheduler};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// cargo bench --bench scheduler
+// Compares `Scheduler::Shared` with `Scheduler::WorkStealing` on many small tasks.
+const TASKS: usize = 100_000;
+const FAN_OUT: usize = 100;
+
+fn main() {
+    for scheduler in &[Scheduler::Shared, Scheduler::WorkStealing] {
+        let pool = Arc::new(
+            Builder::new()
+                .min(Builder::num_cpus())
+                .max(Builder::num_cpus())
+                .scheduler(*scheduler)
+                .build()
+                .unwrap(),
+        );
+        eprintln!(
+            "{:?}:\n  push from outside: {:?}\n  push from workers: {:?}",
+            scheduler,
+            outside(&pool),
+            fan_out(&pool)
+        );
+    }
+}
+
+// 所有任务都从外部线程添加。
+fn outside(pool: &Pool) -> Duration {
+    let count = Arc::new(AtomicUsize::new(0));
+    let st = Instant::now();
+    for _ in 0..TASKS {
+        let count = count.clone();
+        pool.push(move || { count.fetch_add(1, Ordering::Relaxed); });
+    }
+    pool.join_ms(1);
+    assert_eq!(count.load(Ordering::SeqCst), TASKS);
+    st.elapsed()
+}
+
+// 每个任务在工作线程里再添加 FAN_OUT 个小任务。
+fn fan_out(pool: &Arc<Pool>) -> Duration {
+    let count = Arc::new(AtomicUsize::new(0));
+    let st = Instant::now();
+    for _ in 0..TASKS / FAN_OUT {
+        let (pool2, count) = (pool.clone(), count.clone());
+        pool.push(move || for _ in 0..FAN_OUT {
+            let count = count.clone();
+            pool2.push(move || { count.fetch_add(1, Ordering::Relaxed); });
+        });
+    }
+    pool.join_ms(1);
+    assert_eq!(count.load(Ordering::SeqCst), TASKS);
+    st.elapsed()
+}
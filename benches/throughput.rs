@@ -0,0 +1,45 @@
+extern crate poolite;
+use poolite::{Builder, Pool};
+
+use std::time::{Duration, Instant};
+
+// cargo bench --bench throughput
+// Measures the cost of the queue itself: many no-op tasks pushed from one thread, then `join`.
+const TASKS: usize = 200_000;
+const THREADS: usize = 4;
+const ROUNDS: usize = 5;
+
+fn main() {
+    let pool = Builder::new().min(THREADS).max(THREADS).build().unwrap();
+    eprintln!(
+        "{} no-op tasks on {} threads:\n  push: {:?}\n  push_with_priority: {:?}",
+        TASKS,
+        THREADS,
+        best(|| push(&pool)),
+        best(|| push_with_priority(&pool))
+    );
+}
+
+// 取 ROUNDS 次中最快的一次。
+fn best<F: Fn() -> Duration>(f: F) -> Duration {
+    (0..ROUNDS).map(|_| f()).min().unwrap()
+}
+
+fn push(pool: &Pool) -> Duration {
+    let st = Instant::now();
+    for _ in 0..TASKS {
+        pool.push(|| {});
+    }
+    pool.join_ms(1);
+    st.elapsed()
+}
+
+// 混合几个优先级, 每次出队都要比较。
+fn push_with_priority(pool: &Pool) -> Duration {
+    let st = Instant::now();
+    for idx in 0..TASKS {
+        pool.push_with_priority((idx % 4) as u8, || {});
+    }
+    pool.join_ms(1);
+    st.elapsed()
+}
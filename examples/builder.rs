@@ -27,7 +27,7 @@ fn test(msg: i32) {
 
 fn fib(msg: i32) -> i32 {
     match msg {
-        0...2 => 1,
+        0..=2 => 1,
         x => fib(x - 1) + fib(x - 2),
     }
 }
@@ -0,0 +1,24 @@
+extern crate poolite;
+use poolite::Pool;
+
+use std::time::{Duration, Instant};
+
+/// `cargo run --example timer`
+fn main() {
+    let pool = Pool::new().unwrap();
+    let st = Instant::now();
+    for i in 0..10 {
+        pool.push_after(Duration::from_millis(100 * i), move || test(i, st));
+    }
+    // Cancel the last one.
+    let handle = pool.push_after(Duration::from_millis(1000), move || test(10, st));
+    assert!(handle.cancel());
+
+    pool.push_at(st + Duration::from_millis(1100), move || println!("finished at {:?}", st.elapsed()));
+    std::thread::sleep(Duration::from_millis(1200));
+    pool.join(); //wait for the pool
+}
+
+fn test(msg: u64, st: Instant) {
+    println!("key: {}\tafter: {:?}", msg, st.elapsed());
+}
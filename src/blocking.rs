@@ -0,0 +1,59 @@
+// 阻塞任务结束(包括 panic)后计数减一。
+struct BlockingGuard {
+    pool: Weak<Builder>,
+}
+
+impl Drop for BlockingGuard {
+    fn drop(&mut self) {
+        if let Some(inner) = Inner::upgrade(&self.pool) {
+            inner.as_builder().threads_blocking.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Inner {
+    #[inline]
+    pub fn threads_blocking(&self) -> usize {
+        self.as_builder().threads_blocking.load(Ordering::Relaxed)
+    }
+    // 阻塞任务运行时, 线程数上限临时增加(不超过 max_blocking)。
+    #[inline]
+    fn max_effective(&self) -> usize {
+        let blocking = cmp::min(self.threads_blocking(), self.as_builder().max_blocking_get());
+        self.as_builder().max_get().saturating_add(blocking)
+    }
+    fn push_blocking(&self, task: Task) {
+        let pool = self.downgrade();
+        self.push(
+            Box::new(move || {
+                let _guard = Inner::upgrade(&pool).map(|inner| {
+                    inner.as_builder().threads_blocking.fetch_add(1, Ordering::SeqCst);
+                    // 补偿被占用的工作线程。
+                    if inner.tasks_len() > inner.threads_waiting() && inner.threads_future() < inner.max_effective() {
+                        let _ = inner.grow(1);
+                    }
+                    BlockingGuard { pool: inner.downgrade() }
+                });
+                task.call();
+            }),
+            0,
+        )
+    }
+}
+
+impl Pool {
+    /// Appends a task which blocks for a long time(on I/O etc) to the Pool,
+    ///
+    /// while it runs the Pool may spawn threads beyond `Builder::max` up to `Builder::max_blocking`,
+    /// the extra threads exit after the idle `Builder::timeout`.
+    pub fn push_blocking<T>(&self, task: T)
+    where
+        T: Runable + Send + 'static,
+    {
+        self.inner.push_blocking(Box::new(task))
+    }
+    /// Returns the number of the running `Task`s pushed by `Pool::push_blocking()`.
+    pub fn threads_blocking(&self) -> usize {
+        self.inner.threads_blocking()
+    }
+}
@@ -0,0 +1,188 @@
+// A class of `Task`s with a concurrency limit, see `Pool::category()`.
+struct Gate {
+    name: String,
+    limit: usize,
+    running: usize,
+    // 超出限制的任务在这里排队, 不占用工作线程。
+    waiting: VecDeque<Task>,
+}
+
+impl Gate {
+    // 取出可以开始的任务, 并计入 running。
+    fn ready(&mut self) -> Vec<Task> {
+        let mut tasks = Vec::new();
+        while self.running < self.limit {
+            match self.waiting.pop_front() {
+                Some(task) => {
+                    self.running += 1;
+                    tasks.push(task);
+                }
+                None => break,
+            }
+        }
+        tasks
+    }
+}
+
+#[derive(Default)]
+struct Gates {
+    names: HashMap<String, usize>,
+    gates: Vec<Gate>,
+}
+
+impl Gates {
+    fn gate(&mut self, name: &str) -> usize {
+        if let Some(idx) = self.names.get(name) {
+            return *idx;
+        }
+        self.gates.push(Gate {
+            name: name.to_owned(),
+            limit: usize::MAX,
+            running: 0,
+            waiting: VecDeque::new(),
+        });
+        self.names.insert(name.to_owned(), self.gates.len() - 1);
+        self.gates.len() - 1
+    }
+}
+
+// The categories of `Pool::category()`.
+#[derive(Default)]
+struct Categories {
+    gates: Mutex<Gates>,
+}
+
+impl Categories {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Gates> {
+        self.gates.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Debug for Categories {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let gates = self.lock();
+        fmt.debug_map()
+            .entries(gates.gates.iter().map(|g| (&g.name, (g.running, g.waiting.len()))))
+            .finish()
+    }
+}
+
+// 任务结束(包括 panic)后释放名额, 并把等待的任务放入 Pool。
+struct GateGuard {
+    pool: Weak<Builder>,
+    gate: usize,
+}
+
+impl Drop for GateGuard {
+    fn drop(&mut self) {
+        if let Some(inner) = Inner::upgrade(&self.pool) {
+            let tasks = {
+                let mut gates = inner.as_builder().categories.lock();
+                let gate = &mut gates.gates[self.gate];
+                gate.running -= 1;
+                gate.ready()
+            };
+            for task in tasks {
+                inner.push_gated(self.gate, task);
+            }
+        }
+    }
+}
+
+impl Inner {
+    fn push_in(&self, gate: usize, task: Task) {
+        let tasks = {
+            let mut gates = self.as_builder().categories.lock();
+            gates.gates[gate].waiting.push_back(task);
+            gates.gates[gate].ready()
+        };
+        for task in tasks {
+            self.push_gated(gate, task);
+        }
+    }
+    fn push_gated(&self, gate: usize, task: Task) {
+        let guard = GateGuard { pool: self.downgrade(), gate };
+        self.push(
+            Box::new(move || {
+                let _guard = guard;
+                task.call();
+            }),
+            0,
+        )
+    }
+}
+
+/// A category of the `Pool`, returned by `Pool::category()`.
+///
+/// At most `limit` `Task`s of the category run concurrently,
+/// the others stay queued in the category without occupying a worker.
+#[derive(Debug)]
+pub struct Category<'pool> {
+    pool: &'pool Pool,
+    gate: usize,
+}
+
+impl<'pool> Category<'pool> {
+    /// Sets the concurrency limit of the category, default is unlimited.
+    pub fn limit(self, limit: usize) -> Self {
+        assert!(limit != 0, "limit == 0");
+        let tasks = {
+            let mut gates = self.pool.inner.as_builder().categories.lock();
+            gates.gates[self.gate].limit = limit;
+            gates.gates[self.gate].ready()
+        };
+        for task in tasks {
+            self.pool.inner.push_gated(self.gate, task);
+        }
+        self
+    }
+    #[inline]
+    pub fn limit_get(&self) -> usize {
+        self.pool.inner.as_builder().categories.lock().gates[self.gate].limit
+    }
+    #[inline]
+    pub fn name(&self) -> String {
+        self.pool.inner.as_builder().categories.lock().gates[self.gate].name.clone()
+    }
+    /// Returns the number of `Task`s running(or queued in the Pool).
+    #[inline]
+    pub fn running(&self) -> usize {
+        self.pool.inner.as_builder().categories.lock().gates[self.gate].running
+    }
+    /// Returns the number of `Task`s waiting for the limit.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pool.inner.as_builder().categories.lock().gates[self.gate].waiting.len()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Appends a task to the category.
+    pub fn push<T>(&self, task: T)
+    where
+        T: Runable + Send + 'static,
+    {
+        self.pool.inner.push_in(self.gate, Box::new(task))
+    }
+}
+
+impl Pool {
+    /// Returns the category, creates it if it does not exist.
+    pub fn category<S>(&self, name: S) -> Category<'_>
+    where
+        S: AsRef<str>,
+    {
+        let gate = self.inner.as_builder().categories.lock().gate(name.as_ref());
+        Category { pool: self, gate }
+    }
+    /// Appends a task to the category, see `Pool::category()`.
+    pub fn push_in<S, T>(&self, category: S, task: T)
+    where
+        S: AsRef<str>,
+        T: Runable + Send + 'static,
+    {
+        self.category(category).push(task)
+    }
+}
@@ -0,0 +1,313 @@
+/// A fire is missed if it is later than this(ms).
+const MISSED_FIRE_MS: u64 = 1_000;
+/// Gives up searching the next fire time after this many years.
+const CRON_YEARS: u64 = 5;
+
+/// What to do when a cron fire was missed because the process was busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedFire {
+    /// Runs once for all the missed fire times, the default.
+    #[default]
+    FireOnce,
+    /// Skips the missed fire times, waits for the next one.
+    Skip,
+}
+
+/// The cron expression, in UTC.
+///
+/// Six fields separated by space: `second minute hour day-of-month month day-of-week`,
+/// the `second` can be omitted(then it is `0`).
+///
+/// Each field supports `*`, `?`, numbers, ranges(`1-5`), lists(`1,3,5`) and steps(`*/15`, `10-40/10`),
+/// month and day-of-week also accept names(`JAN`, `MON-FRI`), Sunday is `0` or `7`.
+///
+/// If both day-of-month and day-of-week are restricted, it fires when either of them matches.
+///
+/// ```
+/// extern crate poolite;
+/// use poolite::Cron;
+///
+/// fn main() {
+///     // every weekday at 02:30:00 UTC.
+///     let cron = Cron::parse("0 30 2 * * MON-FRI").unwrap();
+///     assert!(cron.next_after(std::time::SystemTime::now()).is_some());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_any: bool,
+    weekdays_any: bool,
+    missed_fire: MissedFire,
+}
+
+/// The error type for `Cron::parse()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronError {
+    expr: String,
+    msg: String,
+}
+
+impl CronError {
+    fn new<S: Into<String>>(expr: &str, msg: S) -> Self {
+        CronError {
+            expr: expr.to_owned(),
+            msg: msg.into(),
+        }
+    }
+}
+
+impl Error for CronError {}
+
+impl Display for CronError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid cron expression '{}': {}", self.expr, self.msg)
+    }
+}
+
+const MONTHS: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+// 字段名, 取值范围, 名称及其起始值。
+struct CronField {
+    name: &'static str,
+    min: u64,
+    max: u64,
+    names: &'static [&'static str],
+    names_from: u64,
+}
+
+impl CronField {
+    fn value(&self, expr: &str, s: &str) -> Result<u64, CronError> {
+        let value = match self.names.iter().position(|n| n.eq_ignore_ascii_case(s)) {
+            Some(idx) => idx as u64 + self.names_from,
+            None => s.parse::<u64>().map_err(|_| CronError::new(expr, format!("invalid {} '{}'", self.name, s)))?,
+        };
+        if value < self.min || value > self.max {
+            return Err(CronError::new(expr, format!("{} '{}' out of range {}-{}", self.name, s, self.min, self.max)));
+        }
+        Ok(value)
+    }
+    // 解析为位图, 第 n 位表示值 n。
+    fn parse(&self, expr: &str, field: &str) -> Result<u64, CronError> {
+        let mut bits = 0;
+        for part in field.split(',') {
+            let (range, step) = match part.find('/') {
+                Some(idx) => {
+                    let step = &part[idx + 1..];
+                    let step = step.parse::<u64>().ok().filter(|s| *s > 0).ok_or_else(|| CronError::new(expr, format!("invalid step '{}'", step)))?;
+                    (&part[..idx], step)
+                }
+                None => (part, 1),
+            };
+            let (lo, hi) = if range == "*" || range == "?" {
+                (self.min, self.max)
+            } else if let Some(idx) = range.find('-') {
+                let (lo, hi) = (self.value(expr, &range[..idx])?, self.value(expr, &range[idx + 1..])?);
+                if lo > hi {
+                    return Err(CronError::new(expr, format!("invalid range '{}'", range)));
+                }
+                (lo, hi)
+            } else {
+                let value = self.value(expr, range)?;
+                // `5/15` 表示从 5 开始每 15。
+                (value, if step > 1 { self.max } else { value })
+            };
+            let mut value = lo;
+            while value <= hi {
+                bits |= 1 << value;
+                value += step;
+            }
+        }
+        Ok(bits)
+    }
+}
+
+impl Cron {
+    /// Parses the cron expression.
+    pub fn parse(expr: &str) -> Result<Self, CronError> {
+        let mut fields = expr.split_whitespace().collect::<Vec<_>>();
+        if fields.len() == 5 {
+            fields.insert(0, "0");
+        }
+        if fields.len() != 6 {
+            return Err(CronError::new(expr, format!("expected 5 or 6 fields, found {}", fields.len())));
+        }
+        let f = |name, min, max| CronField { name, min, max, names: &[], names_from: 0 };
+        let weekdays = CronField {
+            names: &WEEKDAYS,
+            ..f("day-of-week", 0, 7)
+        };
+        let mut cron = Cron {
+            seconds: f("second", 0, 59).parse(expr, fields[0])?,
+            minutes: f("minute", 0, 59).parse(expr, fields[1])?,
+            hours: f("hour", 0, 23).parse(expr, fields[2])?,
+            days: f("day-of-month", 1, 31).parse(expr, fields[3])?,
+            months: CronField {
+                names: &MONTHS,
+                names_from: 1,
+                ..f("month", 1, 12)
+            }.parse(expr, fields[4])?,
+            weekdays: weekdays.parse(expr, fields[5])?,
+            days_any: fields[3] == "*" || fields[3] == "?",
+            weekdays_any: fields[5] == "*" || fields[5] == "?",
+            missed_fire: MissedFire::default(),
+        };
+        // 7 也是周日。
+        if cron.weekdays & (1 << 7) != 0 {
+            cron.weekdays = (cron.weekdays | 1) & !(1 << 7);
+        }
+        Ok(cron)
+    }
+    /// Sets what to do when a fire was missed, default is `MissedFire::FireOnce`.
+    pub fn missed_fire(mut self, missed_fire: MissedFire) -> Self {
+        self.missed_fire = missed_fire;
+        self
+    }
+    #[inline]
+    pub fn missed_fire_get(&self) -> MissedFire {
+        self.missed_fire
+    }
+    fn day_matches(&self, day: u64, weekday: u64) -> bool {
+        let day = self.days & (1 << day) != 0;
+        let weekday = self.weekdays & (1 << weekday) != 0;
+        match (self.days_any, self.weekdays_any) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+    /// Returns the first fire time(in seconds) that is later than `time`,
+    ///
+    /// `None` if it never fires in the next few years(e.g. `0 0 0 30 2 *`).
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let mut ts = secs + 1;
+        let limit = ts + CRON_YEARS * 366 * 86_400;
+        while ts <= limit {
+            let (days, sod) = (ts / 86_400, ts % 86_400);
+            let (year, month, day) = civil_from_days(days);
+            if self.months & (1 << month) == 0 {
+                ts = if month == 12 { days_from_civil(year + 1, 1, 1) } else { days_from_civil(year, month + 1, 1) } * 86_400;
+                continue;
+            }
+            // 1970-01-01 是周四。
+            if !self.day_matches(day, (days + 4) % 7) {
+                ts = (days + 1) * 86_400;
+                continue;
+            }
+            let (hour, minute, second) = (sod / 3600, sod / 60 % 60, sod % 60);
+            if self.hours & (1 << hour) == 0 {
+                ts = days * 86_400 + (hour + 1) * 3600;
+                continue;
+            }
+            if self.minutes & (1 << minute) == 0 {
+                ts = days * 86_400 + hour * 3600 + (minute + 1) * 60;
+                continue;
+            }
+            if self.seconds & (1 << second) == 0 {
+                ts += 1;
+                continue;
+            }
+            return Some(UNIX_EPOCH + Duration::from_secs(ts));
+        }
+        None
+    }
+}
+
+impl FromStr for Cron {
+    type Err = CronError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+// Howard Hinnant's `civil_from_days`, only for the days since 1970-01-01.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+impl Periodic {
+    // 按 cron 表达式执行, 上次执行未结束则跳过本次。
+    fn cron(periodic: Arc<Self>, cron: Arc<Cron>, time: SystemTime) -> Fire {
+        Box::new(move |inner: &Inner| {
+            if periodic.stopped() {
+                return;
+            }
+            let now = SystemTime::now();
+            let missed = now.duration_since(time).map(|late| late > Duration::from_millis(MISSED_FIRE_MS)).unwrap_or(false);
+            // 错过的多个触发时间只算一次。
+            match cron.next_after(cmp::max(now, time)) {
+                Some(next) => inner.schedule_system(next, periodic.stopped.clone(), Self::cron(periodic.clone(), cron.clone(), next)),
+                None => {
+                    periodic.stopped.store(true, Ordering::SeqCst);
+                }
+            }
+            if missed && cron.missed_fire_get() == MissedFire::Skip {
+                debug!("skip the missed cron fire at {:?}", time);
+                return;
+            }
+            Self::run(periodic, inner);
+        })
+    }
+}
+
+impl Inner {
+    // 把 `SystemTime` 换算为 `Instant` 再添加定时器。
+    fn schedule_system(&self, time: SystemTime, done: Arc<AtomicBool>, fire: Fire) {
+        let now = Instant::now();
+        let at = time.duration_since(SystemTime::now()).map(|d| now + d).unwrap_or(now);
+        self.schedule(at, done, fire)
+    }
+}
+
+impl Pool {
+    /// Runs `f` on the Pool at the times of the cron expression(UTC), see `Cron` for the syntax.
+    ///
+    /// The execution is skipped if the previous one is still running.
+    pub fn schedule_cron<F>(&self, expr: &str, f: F) -> Result<ScheduledHandle, CronError>
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        Cron::parse(expr).map(|cron| self.schedule_cron_with(cron, f))
+    }
+    /// Runs `f` on the Pool at the times of the `Cron`, with its `MissedFire` policy.
+    pub fn schedule_cron_with<F>(&self, cron: Cron, f: F) -> ScheduledHandle
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let (periodic, handle) = Periodic::new(f);
+        match cron.next_after(SystemTime::now()) {
+            Some(next) => self.inner.schedule_system(next, handle.stopped.clone(), Periodic::cron(periodic, Arc::new(cron), next)),
+            None => {
+                handle.stop();
+            }
+        }
+        handle
+    }
+}
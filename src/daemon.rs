@@ -0,0 +1,81 @@
+type OnDaemonTick = Box<dyn Fn(&PoolState) + Send + Sync>;
+
+// daemon 线程意外退出(panic)时重新启动。
+struct Restart<'a> {
+    daemon: &'a Inner,
+}
+
+impl<'a> Drop for Restart<'a> {
+    fn drop(&mut self) {
+        if thread::panicking() && !self.daemon.dropped() {
+            error!("the daemon thread panicked, restart it");
+            let _ = self.daemon.spawn_daemon();
+        }
+    }
+}
+
+impl Inner {
+    fn spawn_daemon(&self) -> io::Result<()> {
+        let daemon = self.clone();
+        // 先置位, 避免 run() 返回后 daemon 线程尚未启动。
+        self.as_builder().daemon_alive.store(true, Ordering::SeqCst);
+        let mut b = thread::Builder::new();
+        if let Some(name) = daemon.as_builder().name_get() {
+            b = b.name(name.to_string());
+        }
+
+        b.spawn(move || {
+            let daemon = daemon;
+            let _restart = Restart { daemon: &daemon };
+            let _alive = Alive::add(&daemon.as_builder().daemon_alive);
+            TIMER_THREAD.with(|t| t.set(true));
+            let time = daemon.as_builder().daemon_get().unwrap();
+            let mut drift = 0;
+            loop {
+                // 等待期间处理定时器。
+                daemon.as_builder().timers.service(&daemon, Instant::now() + *time);
+                if daemon.dropped() {
+                    return;
+                }
+                drift = daemon.supervise(drift);
+            }
+        }).map(|_| ())
+            .inspect_err(|e| {
+                self.as_builder().daemon_alive.store(false, Ordering::SeqCst);
+                error!("spawn daemon thread failed: '{}' !", e)
+            })
+    }
+    // daemon 的每个周期: 修正计数, 退出多余的线程, 执行 ScalingPolicy::on_tick() 并发布 PoolState, 返回本次的计数差。
+    fn supervise(&self, last_drift: isize) -> isize {
+        self.as_builder().latency.refresh(Instant::now());
+        let mut state = self.state(0);
+        let future = &self.as_builder().threads_future;
+        // 线程启动或退出时两者短暂不一致, 连续两个周期相同才算漂移。
+        let drift = state.threads_future as isize - state.threads_alive as isize;
+        if drift != 0 {
+            if drift == last_drift {
+                warn!("threads_future({}) drifts from threads_alive({}), corrected", state.threads_future, state.threads_alive);
+                if drift > 0 {
+                    future.fetch_sub(drift as usize, Ordering::SeqCst);
+                } else {
+                    future.fetch_add(-drift as usize, Ordering::SeqCst);
+                }
+                state = self.state(0);
+            }
+        } else if state.threads_future > state.max {
+            self.remove_threads(state.threads_future - state.max);
+        }
+        // Prestart::Lazy 的 Pool 在第一次 push 之前不补足线程。
+        if self.started() {
+            self.apply(self.as_builder().scaling_policy_get().on_tick(&state));
+        }
+        if let Some(f) = self.as_builder().on_daemon_tick.as_ref() {
+            f(&state);
+        }
+        if drift == last_drift {
+            0
+        } else {
+            drift
+        }
+    }
+}
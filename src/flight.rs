@@ -0,0 +1,144 @@
+/// The handle of a single-flight computation, returned by `Pool::singleflight()`.
+///
+/// All the callers requesting the same key while the computation is running share the same handle.
+#[derive(Debug)]
+pub struct SharedHandle<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for SharedHandle<T> {
+    fn clone(&self) -> Self {
+        SharedHandle { shared: self.shared.clone() }
+    }
+}
+
+// `None`: 计算中, `Some(None)`: 计算时 panic 了。
+#[derive(Debug)]
+struct Shared<T> {
+    result: Mutex<Option<Option<T>>>,
+    cond: Condvar,
+}
+
+impl<T: Clone> SharedHandle<T> {
+    fn new() -> Self {
+        let shared = Shared {
+            result: Mutex::new(None),
+            cond: Condvar::new(),
+        };
+        SharedHandle { shared: Arc::new(shared) }
+    }
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Option<Option<T>>> {
+        self.shared.result.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    fn set(&self, result: Option<T>) {
+        *self.lock() = Some(result);
+        self.shared.cond.notify_all();
+    }
+    /// Blocks until the computation finished, returns `None` if it panicked.
+    pub fn wait(&self) -> Option<T> {
+        let mut result = self.lock();
+        loop {
+            if let Some(ref result) = *result {
+                return result.clone();
+            }
+            result = self.shared.cond.wait(result).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+    /// Returns the result if the computation finished, `Some(None)` if it panicked.
+    pub fn try_get(&self) -> Option<Option<T>> {
+        self.lock().clone()
+    }
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.lock().is_some()
+    }
+}
+
+// The running computations of `Pool::singleflight()`, the values are `SharedHandle<T>`.
+#[derive(Default)]
+struct Flights {
+    keys: Mutex<KeyMaps>,
+}
+
+impl Flights {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, KeyMaps> {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Debug for Flights {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Flights").field("len", &self.lock().len()).finish()
+    }
+}
+
+// 计算结束(包括 panic, 或任务未执行就被丢弃)时移除 key 并通知等待者, 没有结果的按 panic 处理。
+struct FlightGuard<K, T>
+where
+    K: Eq + Hash + Send + 'static,
+    T: Clone + Send + 'static,
+{
+    pool: Weak<Builder>,
+    key: K,
+    shared: SharedHandle<T>,
+    result: Option<T>,
+}
+
+impl<K, T> FlightGuard<K, T>
+where
+    K: Eq + Hash + Send + 'static,
+    T: Clone + Send + 'static,
+{
+    #[inline]
+    fn finish(mut self, result: T) {
+        self.result = Some(result);
+    }
+}
+
+impl<K, T> Drop for FlightGuard<K, T>
+where
+    K: Eq + Hash + Send + 'static,
+    T: Clone + Send + 'static,
+{
+    fn drop(&mut self) {
+        // 先移除再通知, 等待者返回后同一 key 会再次执行。
+        if let Some(inner) = Inner::upgrade(&self.pool) {
+            inner.as_builder().flights.lock().get::<K, SharedHandle<T>>().remove(&self.key);
+        }
+        self.shared.set(self.result.take());
+    }
+}
+
+impl Pool {
+    /// Runs `f` on the Pool unless a computation with an equal key(and the same result type) is running,
+    ///
+    /// all the callers requesting the key while it is running receive the result of that one execution.
+    /// If the `Task` never runs(the Pool was dropped), the waiters receive `None`.
+    pub fn singleflight<K, T, F>(&self, key: K, f: F) -> SharedHandle<T>
+    where
+        K: Eq + Hash + Clone + Send + 'static,
+        T: Clone + Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let handle = {
+            let mut flights = self.inner.as_builder().flights.lock();
+            let keys = flights.get::<K, SharedHandle<T>>();
+            if let Some(handle) = keys.get(&key) {
+                return handle.clone();
+            }
+            let handle = SharedHandle::new();
+            keys.insert(key.clone(), handle.clone());
+            handle
+        };
+        let guard = FlightGuard {
+            pool: self.inner.downgrade(),
+            key,
+            shared: handle.clone(),
+            result: None,
+        };
+        self.push(move || guard.finish(f()));
+        handle
+    }
+}
@@ -0,0 +1,282 @@
+/// The node of a `TaskGraph`, returned by `TaskGraph::add_node()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Returns the index of the node, in the order they were added.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// A set of `Task`s with dependencies, run by `Pool::run_graph()`.
+///
+/// Each node is scheduled onto the `Pool` as soon as all its dependencies completed,
+/// the outputs can be passed by the shared states captured in the closures.
+#[derive(Default)]
+pub struct TaskGraph {
+    tasks: Vec<Task>,
+    deps: Vec<Vec<usize>>,
+}
+
+impl Debug for TaskGraph {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("TaskGraph").field("nodes", &self.tasks.len()).field("deps", &self.deps).finish()
+    }
+}
+
+impl TaskGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds a node to the graph.
+    pub fn add_node<T>(&mut self, task: T) -> NodeId
+    where
+        T: Runable + Send + 'static,
+    {
+        self.tasks.push(Box::new(task));
+        self.deps.push(Vec::new());
+        NodeId(self.tasks.len() - 1)
+    }
+    /// Declares that `node` depends on `dep`: `node` starts after `dep` completed.
+    pub fn add_edge(&mut self, dep: NodeId, node: NodeId) -> &mut Self {
+        assert!(dep.0 < self.len() && node.0 < self.len(), "NodeId out of the TaskGraph");
+        if !self.deps[node.0].contains(&dep.0) {
+            self.deps[node.0].push(dep.0);
+        }
+        self
+    }
+    /// Declares that `node` depends on all the `deps`.
+    pub fn depends_on<I>(&mut self, node: NodeId, deps: I) -> &mut Self
+    where
+        I: IntoIterator<Item = NodeId>,
+    {
+        for dep in deps {
+            self.add_edge(dep, node);
+        }
+        self
+    }
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+    // 按拓扑排序(Kahn)检查, 返回处于环中(或依赖于环)的节点。
+    fn check(&self) -> Result<(), GraphError> {
+        let mut pending = self.deps.iter().map(|deps| deps.len()).collect::<Vec<_>>();
+        let dependents = self.dependents();
+        let mut ready = (0..self.len()).filter(|&i| pending[i] == 0).collect::<Vec<_>>();
+        while let Some(i) = ready.pop() {
+            for &d in &dependents[i] {
+                pending[d] -= 1;
+                if pending[d] == 0 {
+                    ready.push(d);
+                }
+            }
+        }
+        let cycle = (0..self.len()).filter(|&i| pending[i] > 0).map(NodeId).collect::<Vec<_>>();
+        if cycle.is_empty() {
+            Ok(())
+        } else {
+            Err(GraphError { cycle })
+        }
+    }
+    fn dependents(&self) -> Vec<Vec<usize>> {
+        let mut dependents = vec![Vec::new(); self.len()];
+        for (i, deps) in self.deps.iter().enumerate() {
+            for &dep in deps {
+                dependents[dep].push(i);
+            }
+        }
+        dependents
+    }
+}
+
+/// The error of `Pool::run_graph()`: the `TaskGraph` has a cycle, no node was run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphError {
+    cycle: Vec<NodeId>,
+}
+
+impl GraphError {
+    /// Returns the nodes in (or depending on) the cycle.
+    #[inline]
+    pub fn cycle(&self) -> &[NodeId] {
+        &self.cycle
+    }
+}
+
+impl Error for GraphError {}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the task graph has a cycle: {:?}", self.cycle.iter().map(|n| n.0).collect::<Vec<_>>())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeState {
+    Waiting,
+    Completed,
+    Panicked,
+    Skipped,
+}
+
+/// The result of `Pool::run_graph()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphReport {
+    states: Vec<NodeState>,
+}
+
+impl GraphReport {
+    fn nodes(&self, state: NodeState) -> Vec<NodeId> {
+        (0..self.states.len()).filter(|&i| self.states[i] == state).map(NodeId).collect()
+    }
+    /// Returns the nodes completed.
+    pub fn completed(&self) -> Vec<NodeId> {
+        self.nodes(NodeState::Completed)
+    }
+    /// Returns the nodes panicked.
+    pub fn panicked(&self) -> Vec<NodeId> {
+        self.nodes(NodeState::Panicked)
+    }
+    /// Returns the nodes not run because one of their dependencies panicked.
+    pub fn skipped(&self) -> Vec<NodeId> {
+        self.nodes(NodeState::Skipped)
+    }
+    /// Returns `true` if all the nodes completed.
+    pub fn is_success(&self) -> bool {
+        self.states.iter().all(|s| *s == NodeState::Completed)
+    }
+}
+
+// 运行中的 TaskGraph。
+struct GraphRun {
+    tasks: Vec<Option<Task>>,
+    dependents: Vec<Vec<usize>>,
+    // 尚未完成的依赖数。
+    pending: Vec<usize>,
+    states: Vec<NodeState>,
+    // 尚未结束(完成, panic 或跳过)的节点数。
+    left: usize,
+    // 已添加到 Pool 的节点, 其任务可能已被取走。
+    ready: Vec<usize>,
+}
+
+impl GraphRun {
+    // 节点结束, 返回可以开始的节点。
+    fn finish(&mut self, node: usize, ok: bool) -> Vec<usize> {
+        self.left -= 1;
+        if !ok {
+            self.states[node] = NodeState::Panicked;
+            self.skip(node);
+            return Vec::new();
+        }
+        self.states[node] = NodeState::Completed;
+        let mut ready = Vec::new();
+        for &d in &self.dependents[node] {
+            self.pending[d] -= 1;
+            if self.pending[d] == 0 && self.states[d] == NodeState::Waiting {
+                ready.push(d);
+            }
+        }
+        ready
+    }
+    fn skip(&mut self, node: usize) {
+        let mut nodes = self.dependents[node].clone();
+        while let Some(d) = nodes.pop() {
+            if self.states[d] == NodeState::Waiting {
+                self.states[d] = NodeState::Skipped;
+                self.tasks[d] = None;
+                self.left -= 1;
+                nodes.extend_from_slice(&self.dependents[d]);
+            }
+        }
+    }
+}
+
+type Graph = Arc<(Mutex<GraphRun>, Condvar)>;
+
+impl Inner {
+    // 任务在开始时才取出, 在工作线程里等待的 run_graph() 也可以先取走。
+    fn push_node(&self, graph: &Graph, node: usize) {
+        graph.0.lock().unwrap_or_else(|e| e.into_inner()).ready.push(node);
+        graph.1.notify_all();
+        let (pool, graph) = (self.downgrade(), graph.clone());
+        self.push(
+            Box::new(move || {
+                let task = graph.0.lock().unwrap_or_else(|e| e.into_inner()).tasks[node].take();
+                if let (Some(task), Some(inner)) = (task, Inner::upgrade(&pool)) {
+                    inner.run_node(&graph, node, task);
+                }
+            }),
+            0,
+        )
+    }
+    fn run_node(&self, graph: &Graph, node: usize, task: Task) {
+        let ok = panic::catch_unwind(AssertUnwindSafe(|| task.call())).is_ok();
+        if !ok {
+            error!("the node {} of the task graph panicked", node);
+        }
+        let ready = {
+            let mut run = graph.0.lock().unwrap_or_else(|e| e.into_inner());
+            let ready = run.finish(node, ok);
+            if run.left == 0 {
+                graph.1.notify_all();
+            }
+            ready
+        };
+        for node in ready {
+            self.push_node(graph, node);
+        }
+    }
+}
+
+impl Pool {
+    /// Runs the `TaskGraph` on the Pool and blocks until all the nodes finished,
+    ///
+    /// the dependents of a panicked node are skipped, returns `Err` without running any node if the graph has a cycle.
+    ///
+    /// Called from a worker of this Pool, the worker runs the ready nodes itself while it waits,
+    /// so it can't deadlock even if all the workers are waiting.
+    pub fn run_graph(&self, graph: TaskGraph) -> Result<GraphReport, GraphError> {
+        graph.check()?;
+        let dependents = graph.dependents();
+        let pending = graph.deps.iter().map(|deps| deps.len()).collect::<Vec<_>>();
+        let ready = (0..graph.len()).filter(|&i| pending[i] == 0).collect::<Vec<_>>();
+        let run = GraphRun {
+            states: vec![NodeState::Waiting; graph.len()],
+            left: graph.len(),
+            tasks: graph.tasks.into_iter().map(Some).collect(),
+            dependents,
+            pending,
+            ready: Vec::new(),
+        };
+        let graph = Graph::new((Mutex::new(run), Condvar::new()));
+        for node in ready {
+            self.inner.push_node(&graph, node);
+        }
+        // 在本 Pool 的工作线程里调用时, 边等边执行就绪的节点, 线程都在等待时也不会死锁。
+        let worker = self.inner.is_worker();
+        let mut run = graph.0.lock().unwrap_or_else(|e| e.into_inner());
+        while run.left > 0 {
+            let task = if worker { run.ready.pop().map(|node| (node, run.tasks[node].take())) } else { None };
+            run = match task {
+                Some((node, Some(task))) => {
+                    drop(run);
+                    self.inner.run_node(&graph, node, task);
+                    graph.0.lock().unwrap_or_else(|e| e.into_inner())
+                }
+                Some((_, None)) => run,
+                None => graph.1.wait(run).unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+        Ok(GraphReport {
+            states: mem::take(&mut run.states),
+        })
+    }
+}
@@ -3,10 +3,10 @@ const TIME_OUT_MS: u64 = 5_000;
 /// Defaults open daemon.
 // const DAEMON: Option<Duration> = Some(Duration::from_millis(TIME_OUT_MS));
 static mut NUM_CPUS: usize = 0;
-static INIT: Once = ONCE_INIT;
+static INIT: Once = Once::new();
 
 /// The Task Box
-pub type Task = Box<Runable + Send + 'static>;
+pub type Task = Box<dyn Runable + Send + 'static>;
 
 /// The `Runable` trait for `FnOnce()`
 pub trait Runable {
@@ -65,17 +65,43 @@ fn fib(msg: i32) -> i32 {
 pub struct Builder {
     name: Option<String>,
     stack_size: Option<usize>,
-    min: usize,
-    max: usize,
-    timeout: Option<Duration>,
-    load_limit: usize,
+    // 运行时可修改, 见 Pool::set_min() 等。
+    min: AtomicUsize,
+    max: AtomicUsize,
+    max_blocking: usize,
+    timeout: AtomicU64,
+    load_limit: AtomicUsize,
     daemon: Option<Duration>,
+    aging: Option<Duration>,
+    scheduler: Scheduler,
+    coalesce: Coalesce,
+    on_expired: Option<Box<dyn Fn(Duration) + Send + Sync + 'static>>,
+    task_warn_after: Option<Duration>,
+    on_task_warn: Option<OnTaskWarn>,
+    scaling: Box<dyn ScalingPolicy>,
+    on_daemon_tick: Option<OnDaemonTick>,
+    spawn_retry: (u32, Duration),
+    prestart: Prestart,
+    on_spawn_error: Option<OnSpawnError>,
+    rate_limit: Option<RateLimiter>,
 
-    mp: Sender<Task>,
-    mc: Receiver<Task>,
+    queue: Queue,
+    locals: Locals,
+    serials: Serials,
+    uniques: Uniques,
+    flights: Flights,
+    categories: Categories,
+    timers: Timers,
+    watchdog: Watchdog,
+    latency: Latency,
     threads_future: AtomicUsize, // contains ready to create, consider create failed
     threads_alive: AtomicUsize, // alive, contains busy with task and wait for Task arrive
     threads_waiting: AtomicUsize, // wait for Task arrive
+    threads_blocking: AtomicUsize, // running the Task of push_blocking()
+    tasks_expired: AtomicUsize,
+    tasks_coalesced: AtomicUsize,
+    started: AtomicBool, // the min threads were spawned, see Prestart
+    spawn_retrying: AtomicBool, // a retry of the failed spawn is scheduled
     daemon_alive: AtomicBool,
     dropped: AtomicBool,
 }
@@ -86,14 +112,39 @@ impl Debug for Builder {
             .field("stack_size", &self.stack_size)
             .field("min", &self.min)
             .field("max", &self.max)
-            .field("timeout", &self.timeout)
+            .field("max_blocking", &self.max_blocking)
+            .field("timeout", &self.timeout_get())
             .field("load_limit", &self.load_limit)
             .field("daemon", &self.daemon)
-            .field("mp", &"Sender<Task>")
-            .field("mc", &"Receiver<Task>")
+            .field("aging", &self.aging)
+            .field("scheduler", &self.scheduler)
+            .field("coalesce", &self.coalesce)
+            .field("on_expired", &self.on_expired.as_ref().map(|_| "Fn(Duration)"))
+            .field("task_warn_after", &self.task_warn_after)
+            .field("on_task_warn", &self.on_task_warn.as_ref().map(|_| "Fn(&str, Option<&str>, Duration)"))
+            .field("rate_limit", &self.rate_limit)
+            .field("scaling", &self.scaling)
+            .field("on_daemon_tick", &self.on_daemon_tick.as_ref().map(|_| "Fn(&PoolState)"))
+            .field("spawn_retry", &self.spawn_retry)
+            .field("prestart", &self.prestart)
+            .field("on_spawn_error", &self.on_spawn_error.as_ref().map(|_| "Fn(&io::Error, u32)"))
+            .field("queue", &self.queue)
+            .field("locals", &self.locals)
+            .field("serials", &self.serials)
+            .field("uniques", &self.uniques)
+            .field("flights", &self.flights)
+            .field("categories", &self.categories)
+            .field("timers", &self.timers)
+            .field("watchdog", &self.watchdog)
+            .field("latency", &self.latency)
             .field("threads_future", &self.threads_future)
             .field("threads_alive", &self.threads_alive)
             .field("threads_waiting", &self.threads_waiting)
+            .field("threads_blocking", &self.threads_blocking)
+            .field("tasks_expired", &self.tasks_expired)
+            .field("tasks_coalesced", &self.tasks_coalesced)
+            .field("started", &self.started)
+            .field("spawn_retrying", &self.spawn_retrying)
             .field("daemon_alive", &self.daemon_alive)
             .field("dropped", &self.dropped)
             .finish()
@@ -115,21 +166,45 @@ impl Builder {
         }
     }
     pub fn new() -> Self {
-        let (mp, mc) = unbounded();
         Self {
-            mp: mp,
-            mc: mc,
+            queue: Queue::new(),
+            locals: Locals::default(),
+            serials: Serials::default(),
+            uniques: Uniques::default(),
+            flights: Flights::default(),
+            categories: Categories::default(),
+            timers: Timers::new(),
+            watchdog: Watchdog::default(),
+            latency: Latency::default(),
             threads_future: AtomicUsize::default(),
             threads_alive: AtomicUsize::default(),
             threads_waiting: AtomicUsize::default(),
+            threads_blocking: AtomicUsize::default(),
+            tasks_expired: AtomicUsize::default(),
+            tasks_coalesced: AtomicUsize::default(),
 
-            min: Self::min_default(),
-            max: Self::max_default(),
-            timeout: Some(Duration::from_millis(TIME_OUT_MS)),
+            min: AtomicUsize::new(Self::min_default()),
+            max: AtomicUsize::new(Self::max_default()),
+            max_blocking: Self::max_default(),
+            timeout: AtomicU64::new(nanos_from(Some(Duration::from_millis(TIME_OUT_MS)))),
             name: None,
             stack_size: None,
-            load_limit: Self::num_cpus() * Self::num_cpus(),
+            load_limit: AtomicUsize::new(Self::num_cpus() * Self::num_cpus()),
             daemon: Some(Duration::from_millis(TIME_OUT_MS)),
+            aging: Some(Duration::from_millis(AGING_MS)),
+            scheduler: Scheduler::default(),
+            coalesce: Coalesce::default(),
+            on_expired: None,
+            task_warn_after: None,
+            on_task_warn: None,
+            rate_limit: None,
+            scaling: Box::new(LoadLimit),
+            on_daemon_tick: None,
+            spawn_retry: (SPAWN_RETRIES, Duration::from_millis(SPAWN_BACKOFF_MS)),
+            prestart: Prestart::default(),
+            on_spawn_error: None,
+            started: AtomicBool::default(),
+            spawn_retrying: AtomicBool::default(),
             daemon_alive: AtomicBool::default(),
             dropped: AtomicBool::default(),
         }
@@ -162,40 +237,53 @@ impl Builder {
         self.stack_size.as_ref()
     }
     /// Sets the minimum number of threads in the Pool，default is `num_cpus()+1`.
+    ///
+    /// With `0` the Pool spawns a thread on the first push and returns to zero threads after the idle `timeout`.
     pub fn min(mut self, min: usize) -> Self {
-        if self.max < min && self.max == Self::max_default() {
-            self.max = min;
+        if self.max_get() < min && self.max_get() == Self::max_default() {
+            *self.max.get_mut() = min;
         }
-        self.min = min;
+        *self.min.get_mut() = min;
         self
     }
     #[inline]
-    pub fn min_get(self: &Self) -> &usize {
-        &self.min
+    pub fn min_get(&self) -> usize {
+        self.min.load(Ordering::Relaxed)
     }
     /// Sets the maximum number of threads in the Pool，default is `(num_cpus()+1)*num_cpus()`.
     pub fn max(mut self, max: usize) -> Self {
-        if self.min > max && self.min == Self::min_default() {
-            self.min = max;
+        if self.min_get() > max && self.min_get() == Self::min_default() {
+            *self.min.get_mut() = max;
         }
-        self.max = max;
+        *self.max.get_mut() = max;
         self
     }
     #[inline]
-    pub fn max_get(&self) -> &usize {
-        &self.max
+    pub fn max_get(&self) -> usize {
+        self.max.load(Ordering::Relaxed)
+    }
+    /// Sets the maximum number of the extra threads beyond `max` for the running `Pool::push_blocking()` `Task`s,
+    ///
+    /// default is the same as the default `max`.
+    pub fn max_blocking(mut self, max_blocking: usize) -> Self {
+        self.max_blocking = max_blocking;
+        self
+    }
+    #[inline]
+    pub fn max_blocking_get(&self) -> usize {
+        self.max_blocking
     }
     /// Sets thread's idle time(ms) except minimum number of threads,default is 5000(ms).
     pub fn timeout_ms(self, timeout: Option<u64>) -> Self {
         self.timeout(timeout.map(Duration::from_millis))
     }
     pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
-        self.timeout = timeout;
+        *self.timeout.get_mut() = nanos_from(timeout);
         self
     }
     #[inline]
-    pub fn timeout_get(&self) -> Option<&Duration> {
-        self.timeout.as_ref()
+    pub fn timeout_get(&self) -> Option<Duration> {
+        nanos_into(self.timeout.load(Ordering::Relaxed))
     }
     /// Sets whether to open the daemon for the Pool, the default is `Some(5000)`(thread's default idle time(ms)).
     ///
@@ -212,16 +300,152 @@ impl Builder {
     pub fn daemon_get(&self) -> Option<&Duration> {
         self.daemon.as_ref()
     }
+    /// Sets the callback receiving a `PoolState` sample every `Builder::daemon`, it runs on the daemon thread.
+    ///
+    /// Each period the daemon also corrects the drifting thread counters, retires the idle threads above `max`
+    /// and restarts itself if it panicked.
+    pub fn on_daemon_tick<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&PoolState) + Send + Sync + 'static,
+    {
+        self.on_daemon_tick = Some(Box::new(f));
+        self
+    }
+    /// Sets how many times to retry spawning the threads failed to spawn, and the delay before the first retry,
+    ///
+    /// the delay doubles for each retry, default is `(3, 100ms)`.
+    /// Only one retry is pending at a time, and the failures of `Pool::add_threads()` are not retried.
+    /// If the Pool has no thread and fails to spawn one, the caller thread runs the `Task` it just pushed,
+    /// except on the daemon(or timer) thread, where the `Task` stays queued for the retry.
+    pub fn spawn_retry(mut self, retries: u32, backoff: Duration) -> Self {
+        self.spawn_retry = (retries, backoff);
+        self
+    }
+    #[inline]
+    pub fn spawn_retry_get(&self) -> (u32, Duration) {
+        self.spawn_retry
+    }
+    /// Sets the callback for the failure to spawn a thread, it receives the error and the number of retries before it.
+    pub fn on_spawn_error<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&io::Error, u32) + Send + Sync + 'static,
+    {
+        self.on_spawn_error = Some(Box::new(f));
+        self
+    }
+    /// Sets when to spawn the `min` threads, default is `Prestart::Eager`.
+    pub fn prestart(mut self, prestart: Prestart) -> Self {
+        self.prestart = prestart;
+        self
+    }
+    #[inline]
+    pub fn prestart_get(&self) -> &Prestart {
+        &self.prestart
+    }
     /// Sets the value of load_limit for the Pool.
     ///
     /// default is `num_cpus() * num_cpus()`.
     pub fn load_limit(mut self, load_limit: usize) -> Self {
-        self.load_limit = load_limit;
+        *self.load_limit.get_mut() = load_limit;
         self
     }
     #[inline]
-    pub fn load_limit_get(&self) -> &usize {
-        &self.load_limit
+    pub fn load_limit_get(&self) -> usize {
+        self.load_limit.load(Ordering::Relaxed)
+    }
+    /// Sets the interval(ms) that raises the priority of a waiting `Task` by one, default is 1000(ms).
+    ///
+    /// You can use `None` to close the aging, then low priority `Task`s may starve.
+    pub fn aging_ms(self, aging: Option<u64>) -> Self {
+        self.aging(aging.map(Duration::from_millis))
+    }
+    pub fn aging(mut self, aging: Option<Duration>) -> Self {
+        self.aging = aging;
+        self
+    }
+    #[inline]
+    pub fn aging_get(&self) -> Option<&Duration> {
+        self.aging.as_ref()
+    }
+    /// Sets how the workers take the `Task`s, default is `Scheduler::Shared`.
+    pub fn scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+    #[inline]
+    pub fn scheduler_get(&self) -> &Scheduler {
+        &self.scheduler
+    }
+    /// Sets what `Pool::push_unique()` does if a `Task` with the same key is still queued, default is `Coalesce::KeepQueued`.
+    pub fn coalesce(mut self, coalesce: Coalesce) -> Self {
+        self.coalesce = coalesce;
+        self
+    }
+    #[inline]
+    pub fn coalesce_get(&self) -> &Coalesce {
+        &self.coalesce
+    }
+    /// Sets the callback for the `Task` dropped because its deadline passed before it started,
+    ///
+    /// it receives how late the `Task` was, and runs on the worker thread.
+    pub fn on_expired<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Duration) + Send + Sync + 'static,
+    {
+        self.on_expired = Some(Box::new(f));
+        self
+    }
+    /// Sets the time after which a running `Task` is reported as stuck by the watchdog, default is `None`(closed).
+    ///
+    /// The watchdog runs on the daemon thread(or the timer thread if the daemon is closed), see `Builder::on_task_warn()`.
+    pub fn task_warn_after(mut self, after: Option<Duration>) -> Self {
+        self.task_warn_after = after;
+        self
+    }
+    #[inline]
+    pub fn task_warn_after_get(&self) -> Option<&Duration> {
+        self.task_warn_after.as_ref()
+    }
+    /// Sets the callback for the stuck `Task`, default logs a warning,
+    ///
+    /// it receives the worker's thread name, the `Task`'s name(see `Pool::push_named()`) and how long it has been running.
+    pub fn on_task_warn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, Option<&str>, Duration) + Send + Sync + 'static,
+    {
+        self.on_task_warn = Some(Box::new(f));
+        self
+    }
+    /// Limits how fast the workers start `Task`s: up to `burst` at once, refilled by `permits_per_sec`, default is `None`(unlimited).
+    ///
+    /// The worker waits for the permit after it took the `Task`, then checks its deadline.
+    pub fn rate_limit(self, permits_per_sec: u32, burst: u32) -> Self {
+        self.rate_limiter(RateLimiter::new(permits_per_sec, burst))
+    }
+    /// Sets the `RateLimiter` for the workers, see `Builder::rate_limit()`.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limit = Some(limiter);
+        self
+    }
+    #[inline]
+    pub fn rate_limit_get(&self) -> Option<&RateLimiter> {
+        self.rate_limit.as_ref()
+    }
+    /// Sets the `ScalingPolicy` deciding when to spawn or retire threads, default is `LoadLimit`.
+    pub fn scaling_policy<P>(mut self, policy: P) -> Self
+    where
+        P: ScalingPolicy + 'static,
+    {
+        self.scaling = Box::new(policy);
+        self
+    }
+    /// Uses the `TargetLatency` `ScalingPolicy`: scales by the p90 time the `Task`s wait in the queue instead of `load_limit`.
+    pub fn target_queue_latency(self, target: Duration) -> Self {
+        self.scaling_policy(TargetLatency::new(target))
+    }
+    #[inline]
+    pub fn scaling_policy_get(&self) -> &dyn ScalingPolicy {
+        &*self.scaling
     }
     pub fn build(self) -> Result<Pool, PoolError> {
         Pool::with_builder(self)
@@ -245,44 +469,39 @@ impl Inner {
     pub fn as_builder(&self) -> &Builder {
         &self.workers
     }
+    // 任务里只持有弱引用, 避免 Pool 被 drop 后仍然无法释放。
+    #[inline]
+    fn downgrade(&self) -> Weak<Builder> {
+        Arc::downgrade(&self.workers)
+    }
+    #[inline]
+    fn upgrade(workers: &Weak<Builder>) -> Option<Self> {
+        workers.upgrade().map(|workers| Self { workers })
+    }
     pub fn run(&mut self) -> io::Result<()> {
         let mut result = Ok(());
         if self.as_builder().daemon_get().is_some() {
-            let daemon = self.clone();
-            let mut b = thread::Builder::new();
-            if let Some(name) = daemon.as_builder().name_get() {
-                b = b.name(name.to_string());
-            }
-
-            result = b.spawn(move || {
-                let daemon = daemon;
-                let min = daemon.as_builder().min_get();
-                let _alive = Alive::add(&daemon.as_builder().daemon_alive);
-                let time = daemon.as_builder().daemon_get().unwrap();
-                loop {
-                    thread::sleep(*time);
-                    if daemon.dropped() {
-                        return;
-                    }
-                    let future = daemon.threads_future();
-                    let add_num = if future < *min { min - future } else { 0 };
-                    let _result = daemon.add_threads(add_num);
-                }
-            }).map(|_| ());
+            result = self.spawn_daemon();
+        }
+        if let Some(after) = self.as_builder().task_warn_after_get() {
+            self.schedule(Instant::now(), Arc::default(), Self::watchdog(*after));
         }
-        self.add_threads(*self.as_builder().min_get()).map_err(
-            |e| e.1,
-        )?;
+        self.prestart()?;
         result
     }
     #[inline]
     pub fn is_empty(&self) -> bool {
         // All threads are waiting and tasks_queue'length is 0.
-        self.threads_waiting() == self.threads_alive() && self.tasks_len() == 0
+        self.as_builder().queue.is_idle(&self.as_builder().threads_alive, &self.as_builder().threads_waiting)
     }
     #[inline]
     pub fn tasks_len(&self) -> usize {
-        self.as_builder().mc.len()
+        let queue = &self.as_builder().queue;
+        queue.len() + queue.locals.load(Ordering::SeqCst) + queue.parked.load(Ordering::SeqCst)
+    }
+    #[inline]
+    pub fn tasks_lens(&self) -> BTreeMap<Priority, usize> {
+        self.as_builder().queue.lens()
     }
     #[inline]
     pub fn threads_future(&self) -> usize {
@@ -297,6 +516,10 @@ impl Inner {
         self.as_builder().threads_waiting.load(Ordering::Relaxed)
     }
     #[inline]
+    pub fn tasks_expired(&self) -> usize {
+        self.as_builder().tasks_expired.load(Ordering::Relaxed)
+    }
+    #[inline]
     pub fn daemon_alive(&self) -> bool {
         self.as_builder().daemon_alive.load(Ordering::Relaxed)
     }
@@ -305,19 +528,142 @@ impl Inner {
         self.as_builder().dropped.load(Ordering::Relaxed)
     }
     #[inline]
-    pub fn push(&self, task: Task) {
-        // 注意min==0 且 load_limit>0 时,线程池里无线程则前 load_limit 个请求会一直阻塞。
-        self.as_builder()
-            .mp
-            .send(task)
-            .log_err(|e| error!("Send Task failed: {}", e.description()))
-            .unwrap();
-        let len = self.threads_future();
-        if len == 0 || len < *self.as_builder().max_get() && self.threads_waiting() == 0 && self.tasks_len() / len > *self.as_builder().load_limit_get() {
-            let _ = self.add_threads(1);
+    pub fn push(&self, task: Task, priority: Priority) {
+        self.push_job(Job::new(task, priority))
+    }
+    fn push_job(&self, job: Job) {
+        if !self.started() {
+            let _ = self.start();
+        }
+        let seq = match self.push_local(job) {
+            Ok(()) => None,
+            Err(job) => Some(self.as_builder().queue.push(job)),
+        };
+        if !self.scale(1) {
+            if let Some(seq) = seq {
+                self.run_on_caller(seq..seq + 1);
+            }
+        }
+    }
+    fn push_batch(&self, jobs: Vec<Job>) {
+        if !self.started() {
+            let _ = self.start();
+        }
+        let pushed = jobs.len();
+        let seqs = self.as_builder().queue.push_batch(jobs);
+        if !self.scale(pushed) {
+            self.run_on_caller(seqs);
+        }
+    }
+    // 根据刚添加的任务数目一次性决定新建多少线程, 见 ScalingPolicy::on_push(), 新建线程失败时返回 false。
+    fn scale(&self, pushed: usize) -> bool {
+        pushed == 0 || self.apply(self.as_builder().scaling_policy_get().on_push(&self.state(pushed)))
+    }
+    pub fn set_min(&self, min: usize) -> Result<(), LimitError> {
+        {
+            // 与 set_max() 在 queue 锁下互斥, min 不会超过 max。
+            let mut jobs = self.as_builder().queue.lock();
+            let max = self.as_builder().max_get();
+            if min > max {
+                return Err(LimitError::new(min, max));
+            }
+            self.as_builder().min.store(min, Ordering::SeqCst);
+            jobs.retire = cmp::min(jobs.retire, self.threads_future().saturating_sub(min));
+        }
+        let future = self.threads_future();
+        if future < min {
+            let _ = self.grow(min - future);
+        }
+        Ok(())
+    }
+    pub fn set_max(&self, max: usize) -> Result<(), LimitError> {
+        let queue = &self.as_builder().queue;
+        let retire = {
+            let mut jobs = queue.lock();
+            let min = self.as_builder().min_get();
+            if max == 0 || min > max {
+                return Err(LimitError::new(min, max));
+            }
+            self.as_builder().max.store(max, Ordering::SeqCst);
+            // 待退出的线程数按新的 max 重新计算: 减小时补足, 增大时取消多余的。
+            let excess = self.threads_future().saturating_sub(self.max_effective());
+            let more = excess > jobs.retire;
+            jobs.retire = excess;
+            more
+        };
+        if retire {
+            queue.cond.notify_all();
+        } else {
+            self.scale(self.tasks_len());
+        }
+        Ok(())
+    }
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        self.as_builder().timeout.store(nanos_from(timeout), Ordering::SeqCst);
+        self.as_builder().queue.wake();
+    }
+    pub fn set_load_limit(&self, load_limit: usize) {
+        self.as_builder().load_limit.store(load_limit, Ordering::SeqCst);
+        self.scale(self.tasks_len());
+    }
+    // 线程数超过 above 时, 当前线程退出, 返回 true。
+    fn retire(&self, above: usize) -> bool {
+        let future = &self.as_builder().threads_future;
+        let mut len = future.load(Ordering::SeqCst);
+        while len > above {
+            match future.compare_exchange(len, len - 1, Ordering::SeqCst, Ordering::SeqCst) {
+                Ok(_) => break,
+                Err(now) => len = now,
+            }
+        }
+        if len <= above {
+            return false;
+        }
+        // 先减计数再检查队列: 与 push 先入队再读计数相对, 刚添加的任务要么被看到, 要么 push 按减少后的线程数新建线程。
+        if self.tasks_len() > 0 {
+            future.fetch_add(1, Ordering::SeqCst);
+            return false;
+        }
+        true
+    }
+    // 要求最多 num 个空闲线程退出, 不低于 min, 返回实际要求退出的数目。
+    pub fn remove_threads(&self, num: usize) -> usize {
+        // 线程数在 threads_waiting 与 queue 锁下才一致, 见 Queue::pop()。
+        self.as_builder().queue.retire(|retire| {
+            let idle = self.threads_waiting().saturating_sub(retire);
+            let above = self.threads_future().saturating_sub(self.as_builder().min_get() + retire);
+            cmp::min(num, cmp::min(idle, above))
+        })
+    }
+    // 等待 Builder::rate_limit() 和任务自己的限速许可。
+    fn permit(&self, job: &Job) {
+        for limiter in self.as_builder().rate_limit_get().into_iter().chain(job.limiter.as_deref()) {
+            thread::sleep(limiter.reserve());
+        }
+    }
+    // 截止时间已过的任务丢弃并计数, 返回 true。
+    fn expire(&self, job: &Job, now: Instant) -> bool {
+        match job.expired(now) {
+            Some(late) => {
+                self.as_builder().tasks_expired.fetch_add(1, Ordering::Relaxed);
+                debug!("drop the Task expired {:?} ago", late);
+                if let Some(f) = self.as_builder().on_expired.as_ref() {
+                    f(late);
+                }
+                true
+            }
+            None => false,
         }
     }
     pub fn add_threads(&self, add_num: usize) -> Result<(), (usize, io::Error)> {
+        self.spawn_threads(add_num, None)
+    }
+    // Pool 自己新建线程, 失败后由定时器重试, 见 Builder::spawn_retry()。
+    fn grow(&self, add_num: usize) -> Result<(), (usize, io::Error)> {
+        self.spawn_threads(add_num, Some(0))
+    }
+    // attempt 为 None 时不重试(Pool::add_threads() 的错误直接返回给调用者)。
+    fn spawn_threads(&self, add_num: usize, attempt: Option<u32>) -> Result<(), (usize, io::Error)> {
         self.as_builder().threads_future.fetch_add(
             add_num,
             Ordering::SeqCst,
@@ -325,9 +671,10 @@ impl Inner {
         for idx in 0..add_num {
             if let Err(e) = self.add_thread() {
                 self.as_builder().threads_future.fetch_sub(
-                    add_num - idx - 1,
+                    add_num - idx,
                     Ordering::SeqCst,
                 );
+                self.spawn_failed(&e, attempt, add_num - idx);
                 return Err((idx + 1, e));
             }
         }
@@ -350,60 +697,88 @@ impl Inner {
         let spawn_result = thread.spawn(move || {
             // 对线程计数.
             let _threads_counter = Counter::add(&worker.as_builder().threads_alive);
+            let mut future = FutureCounter::new(&worker.as_builder().threads_future);
 
-            let min = worker.as_builder().min_get();
-            let mut task: Task;
-            if let Some(timeout) = worker.as_builder().timeout_get() {
-                loop {
-                    loop {
-                        if worker.dropped() {
-                            return;
-                        }
-                        // 对在等候的线程计数.
-                        let _threads_waited_counter = Counter::add(&worker.as_builder().threads_waiting);
-                        match worker.as_builder().mc.recv_timeout(*timeout) {
-                            Ok(t) => {
-                                task = t;
-                                break;
+            WORKER.with(|w| w.set(worker.id()));
+            let aging = worker.as_builder().aging_get();
+            let latency = worker.as_builder().scaling_policy_get().uses_queue_latency();
+            let id = worker.as_builder().watchdog.id();
+            let local = match worker.as_builder().scheduler_get() {
+                Scheduler::WorkStealing => Some(worker.register_local(id)),
+                Scheduler::Shared => None,
+            };
+            loop {
+                let job = loop {
+                    if worker.dropped() {
+                        return;
+                    }
+                    if let Some(job) = local.as_ref().and_then(|l| worker.take_local(l)) {
+                        break job;
+                    }
+                    // 对在等候的线程计数.
+                    match worker.as_builder().queue.pop(|| worker.as_builder().timeout_get(), aging, &worker.as_builder().threads_waiting) {
+                        Ok(job) => break job,
+                        Err(PopError::Timeout) => match worker.as_builder().scaling_policy_get().on_idle_timeout(&worker.state(0)) {
+                            Scaling::Retire(_) if worker.retire(worker.as_builder().min_get()) => return future.retired(),
+                            Scaling::Retire(_) => {}
+                            scaling => {
+                                worker.apply(scaling);
                             }
-                            Err(RecvTimeoutError::Timeout) => {
-                                if !worker.as_builder().mc.is_empty() && worker.threads_future() > *min {
-                                    return;
-                                }
+                        },
+                        Err(PopError::Retire) => {
+                            if worker.retire(worker.as_builder().min_get()) {
+                                return future.retired();
                             }
-                            _ => {
-                                return;
+                        }
+                        Err(PopError::Closed) => return,
+                        Err(PopError::Steal) => {
+                            if let Some(job) = local.as_ref().and_then(|l| worker.steal(l)) {
+                                break job;
                             }
                         }
                     }
-                    task.call();
+                };
+                if latency {
+                    let now = Instant::now();
+                    worker.as_builder().latency.record(now, now.saturating_duration_since(job.pushed));
                 }
-            } else {
-                loop {
-                    loop {
-                        if worker.dropped() {
-                            return;
-                        }
-                        let _threads_waited_counter = Counter::add(&worker.as_builder().threads_waiting);
-                        if let Ok(t) = worker.as_builder().mc.recv() {
-                            task = t;
-                            break;
-                        } else {
-                            return;
-                        }
-                    }
-                    task.call();
+                // 任务自己的许可未到时交给定时器, 不占用工作线程。
+                let job = match worker.park(job) {
+                    Some(job) => job,
+                    None => continue,
+                };
+                // 等到许可后再检查截止时间。
+                worker.permit(&job);
+                if worker.expire(&job, Instant::now()) {
+                    continue;
                 }
+                let _busy = worker.as_builder().task_warn_after_get().map(|_| worker.as_builder().watchdog.busy(id, job.name.as_ref()));
+                job.task.call();
             }
         }); //spawn 线程结束。
 
         spawn_result.map(|_| ()).log_err(|e| {
-            error!("add thread failed: '{}' !", e.description())
+            error!("add thread failed: '{}' !", e)
         })
     }
 }
 
 
+// Option<Duration> 存为纳秒数, u64::MAX 表示 None。
+#[inline]
+fn nanos_from(duration: Option<Duration>) -> u64 {
+    duration.map_or(u64::MAX, |d| cmp::min(d.as_nanos(), u64::MAX as u128 - 1) as u64)
+}
+
+#[inline]
+fn nanos_into(nanos: u64) -> Option<Duration> {
+    if nanos == u64::MAX {
+        None
+    } else {
+        Some(Duration::from_nanos(nanos))
+    }
+}
+
 // 通过作用域对线程数目计数。
 struct Counter<'a> {
     count: &'a AtomicUsize,
@@ -413,7 +788,7 @@ impl<'a> Counter<'a> {
     #[inline]
     fn add(count: &'a AtomicUsize) -> Counter<'a> {
         count.fetch_add(1, Ordering::Release);
-        Counter { count: count }
+        Counter { count }
     }
 }
 
@@ -423,6 +798,31 @@ impl<'a> Drop for Counter<'a> {
         self.count.fetch_sub(1, Ordering::Release);
     }
 }
+// 线程退出(包括 panic)时 threads_future 减一, 已通过 Inner::retire() 减过的除外。
+struct FutureCounter<'a> {
+    count: &'a AtomicUsize,
+    retired: bool,
+}
+
+impl<'a> FutureCounter<'a> {
+    #[inline]
+    fn new(count: &'a AtomicUsize) -> FutureCounter<'a> {
+        FutureCounter { count, retired: false }
+    }
+    #[inline]
+    fn retired(&mut self) {
+        self.retired = true;
+    }
+}
+
+impl<'a> Drop for FutureCounter<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        if !self.retired {
+            self.count.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
 // 通过作用域对Daemon状态管理。
 struct Alive<'a> {
     state: &'a AtomicBool,
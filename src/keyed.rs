@@ -0,0 +1,95 @@
+// The `Task`s waiting for their keys, see `Pool::push_keyed()`.
+#[derive(Default)]
+struct Serials {
+    // key 存在表示有任务正在执行(或已入队), 其后的任务在这里排队; 哈希值相同的 key 共用一个队列。
+    keys: Mutex<HashMap<u64, VecDeque<Task>>>,
+}
+
+impl Serials {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, HashMap<u64, VecDeque<Task>>> {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    fn len(&self) -> usize {
+        self.lock().values().map(|tasks| tasks.len()).sum()
+    }
+}
+
+impl Debug for Serials {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let keys = self.lock().len();
+        fmt.debug_struct("Serials").field("keys", &keys).field("len", &self.len()).finish()
+    }
+}
+
+// 任务结束(包括 panic)后把同一 key 的下一个任务放入 Pool。
+struct SerialGuard {
+    pool: Weak<Builder>,
+    key: u64,
+}
+
+impl Drop for SerialGuard {
+    fn drop(&mut self) {
+        if let Some(inner) = Inner::upgrade(&self.pool) {
+            let next = {
+                let mut keys = inner.as_builder().serials.lock();
+                let next = keys.get_mut(&self.key).and_then(|tasks| tasks.pop_front());
+                if next.is_none() {
+                    keys.remove(&self.key);
+                }
+                next
+            };
+            if let Some(task) = next {
+                inner.push_serial(self.key, task);
+            }
+        }
+    }
+}
+
+#[inline]
+fn hash<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Inner {
+    fn push_keyed(&self, key: u64, task: Task) {
+        {
+            let mut keys = self.as_builder().serials.lock();
+            if let Some(tasks) = keys.get_mut(&key) {
+                tasks.push_back(task);
+                return;
+            }
+            keys.insert(key, VecDeque::new());
+        }
+        self.push_serial(key, task)
+    }
+    fn push_serial(&self, key: u64, task: Task) {
+        let guard = SerialGuard { pool: self.downgrade(), key };
+        self.push(
+            Box::new(move || {
+                let _guard = guard;
+                task.call();
+            }),
+            0,
+        )
+    }
+}
+
+impl Pool {
+    /// Appends a task to the Pool with the key,
+    ///
+    /// the `Task`s sharing a key never run concurrently and run in the order they were pushed,
+    /// while the `Task`s of different keys run in parallel.
+    ///
+    /// The keys are compared by their 64-bit hash: two different keys with the same hash are
+    /// serialised together, which only costs parallelism.
+    pub fn push_keyed<K, T>(&self, key: K, task: T)
+    where
+        K: Hash,
+        T: Runable + Send + 'static,
+    {
+        self.inner.push_keyed(hash(&key), Box::new(task))
+    }
+}
@@ -0,0 +1,68 @@
+/// A named queue of the `Pool`, returned by `Pool::queue()`.
+///
+/// The workers take `Task`s from the named queues in weighted round-robin,
+/// so a burst in one queue can not starve the others.
+#[derive(Debug)]
+pub struct TaskQueue<'pool> {
+    pool: &'pool Pool,
+    lane: usize,
+}
+
+impl<'pool> TaskQueue<'pool> {
+    /// Sets the weight of the queue, default is `1`:
+    ///
+    /// a queue with weight `3` takes up to 3 `Task`s per round while the others have `Task`s waiting.
+    pub fn weight(self, weight: usize) -> Self {
+        assert!(weight != 0, "weight == 0");
+        self.pool.inner.as_builder().queue.lock().lanes[self.lane].weight = weight;
+        self
+    }
+    #[inline]
+    pub fn weight_get(&self) -> usize {
+        self.pool.inner.as_builder().queue.lock().lanes[self.lane].weight
+    }
+    #[inline]
+    pub fn name(&self) -> String {
+        self.pool.inner.as_builder().queue.lock().lanes[self.lane].name.clone()
+    }
+    /// Returns the length of the queue.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pool.inner.as_builder().queue.lock().lanes[self.lane].len
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Appends a task to the queue.
+    pub fn push<T>(&self, task: T)
+    where
+        T: Runable + Send + 'static,
+    {
+        self.push_with_priority(0, task)
+    }
+    /// Appends a task to the queue with the given `Priority`, it only takes effect inside the queue.
+    pub fn push_with_priority<T>(&self, priority: Priority, task: T)
+    where
+        T: Runable + Send + 'static,
+    {
+        self.pool.inner.push_job(Job::new(Box::new(task), priority).lane(self.lane))
+    }
+}
+
+impl Pool {
+    /// Returns the named queue, creates it if it does not exist,
+    ///
+    /// `Pool::push()` uses the queue named `DEFAULT_QUEUE`.
+    pub fn queue<S>(&self, name: S) -> TaskQueue<'_>
+    where
+        S: AsRef<str>,
+    {
+        let lane = self.inner.as_builder().queue.lock().lane(name.as_ref());
+        TaskQueue { pool: self, lane }
+    }
+    /// Returns the length of each named queue.
+    pub fn queues_len(&self) -> BTreeMap<String, usize> {
+        self.inner.as_builder().queue.lanes_len()
+    }
+}
@@ -0,0 +1,119 @@
+const LATENCY_SAMPLES: usize = 128;
+const LATENCY_WINDOW_MS: u64 = 1000;
+// 每记录多少个样本重新计算一次 p90。
+const LATENCY_REFRESH: usize = 8;
+
+// The recent queue wait time of the `Task`s, see `PoolState::queue_latency`.
+#[derive(Default)]
+struct Latency {
+    samples: Mutex<VecDeque<(Instant, Duration)>>,
+    // p90 的纳秒数加一, 0 表示没有样本。
+    p90: AtomicU64,
+    recorded: AtomicUsize,
+}
+
+impl Latency {
+    // 竞争时丢弃样本, 不阻塞工作线程。
+    fn record(&self, now: Instant, wait: Duration) {
+        if let Ok(mut samples) = self.samples.try_lock() {
+            if samples.len() == LATENCY_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back((now, wait));
+            if self.recorded.fetch_add(1, Ordering::Relaxed).is_multiple_of(LATENCY_REFRESH) {
+                self.update(&mut samples, now);
+            }
+        }
+    }
+    // 丢弃过期的样本并重新计算, 由 daemon 线程定期调用。
+    fn refresh(&self, now: Instant) {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        self.update(&mut samples, now);
+    }
+    fn update(&self, samples: &mut VecDeque<(Instant, Duration)>, now: Instant) {
+        let window = Duration::from_millis(LATENCY_WINDOW_MS);
+        while samples.front().is_some_and(|s| now.saturating_duration_since(s.0) > window) {
+            samples.pop_front();
+        }
+        let mut waits = samples.iter().map(|s| s.1).collect::<Vec<_>>();
+        let p90 = if waits.is_empty() {
+            0
+        } else {
+            let idx = (waits.len() * 9).div_ceil(10) - 1;
+            let (_, p90, _) = waits.select_nth_unstable(idx);
+            p90.as_nanos().min(u64::MAX as u128 - 1) as u64 + 1
+        };
+        self.p90.store(p90, Ordering::Relaxed);
+    }
+    #[inline]
+    fn p90(&self) -> Option<Duration> {
+        match self.p90.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos - 1)),
+        }
+    }
+}
+
+impl Debug for Latency {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Latency").field("p90", &self.p90()).finish()
+    }
+}
+
+/// The `ScalingPolicy` keeps the p90 queue wait time(see `PoolState::queue_latency`) under the target,
+///
+/// it spawns threads while the p90 exceeds the target, and retires the idle ones while it is below half of the target.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetLatency {
+    target: Duration,
+}
+
+impl TargetLatency {
+    pub fn new(target: Duration) -> Self {
+        TargetLatency { target }
+    }
+    #[inline]
+    pub fn target_get(&self) -> &Duration {
+        &self.target
+    }
+    #[inline]
+    fn above(&self, state: &PoolState) -> bool {
+        state.queue_latency.is_some_and(|p90| p90 > self.target)
+    }
+    // 没有样本(空闲)也算作低于目标。
+    #[inline]
+    fn below(&self, state: &PoolState) -> bool {
+        state.queue_latency.is_none_or(|p90| p90 < self.target / 2)
+    }
+}
+
+impl ScalingPolicy for TargetLatency {
+    fn on_push(&self, state: &PoolState) -> Scaling {
+        if state.threads_future == 0 || state.pushed > state.threads_waiting && self.above(state) {
+            Scaling::Spawn(1)
+        } else {
+            Scaling::Keep
+        }
+    }
+    fn on_idle_timeout(&self, state: &PoolState) -> Scaling {
+        if state.threads_future > state.max || self.below(state) && state.threads_future > state.min {
+            Scaling::Retire(1)
+        } else {
+            Scaling::Keep
+        }
+    }
+    fn on_tick(&self, state: &PoolState) -> Scaling {
+        if state.threads_future < state.min {
+            Scaling::Spawn(state.min - state.threads_future)
+        } else if self.above(state) && state.tasks_len > 0 {
+            Scaling::Spawn(1)
+        } else if self.below(state) && state.threads_waiting > 0 && state.threads_future > state.min {
+            Scaling::Retire(1)
+        } else {
+            Scaling::Keep
+        }
+    }
+    fn uses_queue_latency(&self) -> bool {
+        true
+    }
+}
@@ -9,7 +9,7 @@ On Cargo.toml:
 
 ```toml
  [dependencies]
- poolite = "0.7.1"
+ poolite = "0.8"
 ```
 
 ## Documentation  
@@ -19,6 +19,10 @@ or
 
 * Run `cargo doc --open` after modified the toml file.
 
+## Upgrading from 0.7
+* `Builder::min_get()`, `max_get()` and `load_limit_get()` return `usize`, `timeout_get()` returns `Option<Duration>`:
+  the running Pool can change them, see `Pool::set_min()`, `set_max()`, `set_timeout()` and `set_load_limit()`.
+
 ## Base usage
 ```
 extern crate poolite;
@@ -39,7 +43,7 @@ fn test(msg: i32) {
 
 fn fib(msg: i32) -> i32 {
     match msg {
-        0...2 => 1,
+        0..=2 => 1,
         x => fib(x - 1) + fib(x - 2),
     }
 }
@@ -52,7 +56,7 @@ use poolite::Pool;
 
 fn main() {
     let pool = Pool::new().unwrap();
-    let mut array = (0..100usize).into_iter().map(|i| (i, 0)).collect::<Vec<_>>();
+    let mut array = (0..100usize).map(|i| (i, 0)).collect::<Vec<_>>();
 
     // scoped method will waiting scoped's task running finish.
     pool.scoped(|scope| for i in array.iter_mut() {
@@ -71,19 +75,27 @@ fn main() {
 #[macro_use]
 extern crate log;
 extern crate mxo_env_logger;
-extern crate crossbeam_channel;
 extern crate num_cpus;
 
-use crossbeam_channel::{unbounded, Sender, Receiver, RecvTimeoutError};
 use mxo_env_logger::{init, LogErr};
 
-use std::sync::atomic::{Ordering, AtomicUsize, AtomicBool};
-use std::sync::{Arc, Once, ONCE_INIT};
+use std::sync::atomic::{Ordering, AtomicUsize, AtomicU64, AtomicBool};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::any::{Any, TypeId};
 use std::fmt::{self, Debug, Display};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::mem::transmute;
 use std::marker::PhantomData;
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+use std::mem;
 use std::error::Error;
+use std::str::FromStr;
+use std::panic::{self, AssertUnwindSafe};
+use std::cmp;
 use std::thread;
 use std::io;
 unsafe impl Send for Pool {}
@@ -100,13 +112,13 @@ impl Pool {
        Self::with_builder(Builder::default())
     }
     pub fn with_builder(b: Builder) -> Result<Self, PoolError> {
-        assert!(b.max >= b.min, "min > max");
-        assert!(b.max != 0, "max == 0");
+        assert!(b.max_get() >= b.min_get(), "min > max");
+        assert!(b.max_get() != 0, "max == 0");
         let _ = init();        
 
         let mut new =  Pool { inner: Inner::with_builder(b) };
 
-        match (&mut new).inner.run() {
+        match new.inner.run() {
             Ok(_) => Ok(new),
             Err(e) => Err(PoolError::new(new, e)),
         }
@@ -123,6 +135,10 @@ impl Pool {
     pub fn tasks_len(&self) -> usize {
         self.inner.tasks_len()
     }
+    /// Returns the length of the tasks_queue for each `Priority` that has `Task`s waiting.
+    pub fn tasks_lens(&self) -> BTreeMap<Priority, usize> {
+        self.inner.tasks_lens()
+    }
     // #[doc(hidden)]
     /// Contains the number of ready to create
     pub fn threads_future(&self) -> usize {
@@ -136,6 +152,10 @@ impl Pool {
     pub fn threads_waiting(&self) -> usize {
         self.inner.threads_waiting()
     }
+    /// Returns the number of `Task`s dropped because their deadlines passed before they started.
+    pub fn tasks_expired(&self) -> usize {
+        self.inner.tasks_expired()
+    }
     /// The daemon thread's status
     pub fn daemon_alive(&self) -> bool {
         self.inner.daemon_alive()
@@ -151,9 +171,84 @@ impl Pool {
     where
         T: Runable + Send + 'static,
     {
-        self.inner.push(
-            Box::new(task) as Box<Runable + Send + 'static>,
-        )
+        self.push_with_priority(0, task)
+    }
+    /// Appends a task to the Pool with the given `Priority`,
+    ///
+    /// the `Task` with larger priority runs first, and a waiting `Task` gains one priority per `Builder::aging`.
+    pub fn push_with_priority<T>(&self, priority: Priority, task: T)
+    where
+        T: Runable + Send + 'static,
+    {
+        self.inner.push(Box::new(task) as Task, priority)
+    }
+    /// Appends the tasks to the Pool at once,
+    ///
+    /// it wakes the waiting threads and decides how many threads to spawn only once for the whole batch.
+    pub fn push_batch<I, T>(&self, tasks: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: Runable + Send + 'static,
+    {
+        self.inner.push_batch(tasks.into_iter().map(|task| Job::new(Box::new(task), 0)).collect())
+    }
+    /// Appends a task with the name to the Pool, the name is reported by the watchdog, see `Builder::task_warn_after()`.
+    pub fn push_named<S, T>(&self, name: S, task: T)
+    where
+        S: Into<String>,
+        T: Runable + Send + 'static,
+    {
+        self.inner.push_job(Job::new(Box::new(task), 0).name(name.into()))
+    }
+    /// Appends a task to the Pool that must start before the `deadline`,
+    ///
+    /// otherwise it is dropped and counted in `tasks_expired()`, see `Builder::on_expired()`.
+    pub fn push_with_deadline<T>(&self, deadline: Instant, task: T)
+    where
+        T: Runable + Send + 'static,
+    {
+        self.inner.push_job(Job::new(Box::new(task), 0).deadline(deadline))
+    }
+    /// Appends a task to the Pool after the `delay`, the `TimerHandle` can cancel it before that.
+    ///
+    /// The timers are serviced by the daemon thread, or a timer thread if the daemon is closed.
+    pub fn push_after<T>(&self, delay: Duration, task: T) -> TimerHandle
+    where
+        T: Runable + Send + 'static,
+    {
+        self.push_at(Instant::now() + delay, task)
+    }
+    /// Appends a task to the Pool no earlier than the `Instant`, the `TimerHandle` can cancel it before that.
+    pub fn push_at<T>(&self, at: Instant, task: T) -> TimerHandle
+    where
+        T: Runable + Send + 'static,
+    {
+        self.inner.push_at(at, Box::new(task) as Task)
+    }
+    /// Sets the minimum number of threads of the running Pool, spawns the missing threads at once.
+    ///
+    /// Returns `Err` and changes nothing if `min > max`.
+    pub fn set_min(&self, min: usize) -> Result<(), LimitError> {
+        self.inner.set_min(min)
+    }
+    /// Sets the maximum number of threads of the running Pool, retires the idle threads above it at once,
+    /// the busy ones after their `Task`s.
+    ///
+    /// Returns `Err` and changes nothing if `min > max` or `max == 0`.
+    pub fn set_max(&self, max: usize) -> Result<(), LimitError> {
+        self.inner.set_max(max)
+    }
+    /// Sets the idle time of the threads of the running Pool, the waiting threads take it at once.
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        self.inner.set_timeout(timeout)
+    }
+    /// Sets the load_limit of the running Pool, spawns threads at once if the queued `Task`s exceed it.
+    pub fn set_load_limit(&self, load_limit: usize) {
+        self.inner.set_load_limit(load_limit)
+    }
+    /// Asks up to `num` idle threads to exit, never below `min`, returns the number of threads asked.
+    pub fn remove_threads(&self, num: usize) -> usize {
+        self.inner.remove_threads(num)
     }
     /// Manually add the number of threads to `Pool`
     pub fn add_threads(&self, add_num: usize) -> Result<(), (usize, io::Error)> {
@@ -176,11 +271,32 @@ impl Drop for Pool {
         self.inner.as_builder().dropped.store(
             true,
             Ordering::SeqCst,
-        )
+        );
+        self.inner.as_builder().queue.close();
+        self.inner.as_builder().timers.close();
     }
 }
 
 include!("inner.rs");
+include!("queue.rs");
+include!("timer.rs");
+include!("schedule.rs");
+include!("cron.rs");
+include!("watchdog.rs");
+include!("steal.rs");
+include!("keyed.rs");
+include!("lane.rs");
+include!("unique.rs");
+include!("flight.rs");
+include!("graph.rs");
+include!("rate.rs");
+include!("category.rs");
+include!("blocking.rs");
+include!("scaling.rs");
+include!("latency.rs");
+include!("daemon.rs");
+include!("spawn.rs");
+include!("prestart.rs");
 include!("scope.rs");
 
 /// The error type for the pool's `run()` if the pool spawning the daemon thread fails.
@@ -193,10 +309,7 @@ pub struct PoolError {
 impl PoolError {
     #[inline]
     fn new(pool: Pool, error: std::io::Error) -> Self {
-        PoolError {
-            pool: pool,
-            error: error,
-        }
+        PoolError { pool, error }
     }
     ///  Into `Pool`
     #[inline]
@@ -211,22 +324,47 @@ impl PoolError {
 }
 
 impl Error for PoolError {
-    fn description(&self) -> &str {
-        self.error.description()
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
     }
 }
 
 impl Display for PoolError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use std::error::Error;
         write!(
             f,
             "PoolError {{ pool : Pool, err : {} }}",
-            self.error.description()
+            self.error
         )
     }
 }
 
+/// The error type for `Pool::set_min()` and `Pool::set_max()`: the limits would be `min > max` or `max == 0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitError {
+    min: usize,
+    max: usize,
+}
+
+impl LimitError {
+    #[inline]
+    fn new(min: usize, max: usize) -> Self {
+        LimitError { min, max }
+    }
+    /// Returns the rejected `(min, max)`.
+    #[inline]
+    pub fn limits(&self) -> (usize, usize) {
+        (self.min, self.max)
+    }
+}
+
+impl Error for LimitError {}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid thread limits: min {} max {}", self.min, self.max)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -255,16 +393,16 @@ mod tests {
         let max0 = Builder::max_default();
 
         let p0 = Builder::new().max(min0 - 1);
-        assert_eq!(min0 - 1, p0.max);
-        assert_eq!(min0 - 1, p0.min);
+        assert_eq!(min0 - 1, p0.max_get());
+        assert_eq!(min0 - 1, p0.min_get());
 
         let p1 = Builder::new().min(max0 + 1);
-        assert_eq!(p1.min, max0 + 1);
-        assert_eq!(p1.max, max0 + 1);
+        assert_eq!(p1.min_get(), max0 + 1);
+        assert_eq!(p1.max_get(), max0 + 1);
 
         let p2 = Builder::new().min(max0).max(min0);
-        assert_eq!(p2.min, max0);
-        assert_eq!(p2.max, min0);
+        assert_eq!(p2.min_get(), max0);
+        assert_eq!(p2.max_get(), min0);
     }
     #[test]
     fn fn_fnmut_fnonce_closure() {
@@ -280,9 +418,9 @@ mod tests {
         fn fno(msg: String) {
             println!("{}", msg);
         }
-        let mut str = std::env::args().nth(0).unwrap();
-        let str1 = std::env::args().nth(0).unwrap();
-        let str2 = std::env::args().nth(0).unwrap();
+        let mut str = std::env::args().next().unwrap();
+        let str1 = std::env::args().next().unwrap();
+        let str2 = std::env::args().next().unwrap();
 
         let pool = Pool::new().unwrap();
         pool.push(fnn);
@@ -302,19 +440,19 @@ mod tests {
     fn pool() {
         let pool = Pool::new().unwrap();
         assert!(Builder::num_cpus() >= 1);
-        assert_eq!(*pool.as_builder().min_get(), Builder::min_default());
+        assert_eq!(pool.as_builder().min_get(), Builder::min_default());
         assert_eq!(
-            *pool.as_builder().max_get(),
+            pool.as_builder().max_get(),
             Builder::max_default()
         );
         assert_eq!(
             pool.as_builder().timeout_get(),
-            Some(&Duration::from_millis(TIME_OUT_MS))
+            Some(Duration::from_millis(TIME_OUT_MS))
         );
         assert!(pool.as_builder().name_get().is_none());
         assert!(pool.as_builder().stack_size_get().is_none());
         assert_eq!(
-            *pool.as_builder().load_limit_get(),
+            pool.as_builder().load_limit_get(),
             Builder::num_cpus() * Builder::num_cpus()
         );
 
@@ -325,7 +463,7 @@ mod tests {
         assert!(pool.daemon_alive());
         assert!(!pool.dropped());
 
-        let array = (0..33usize).into_iter().map(|i| (i, 0)).collect::<Vec<_>>();
+        let array = (0..33usize).map(|i| (i, 0)).collect::<Vec<_>>();
 
         let map = Arc::new(Mutex::new(array));
         for i in 0..33 {
@@ -349,19 +487,19 @@ mod tests {
 
         assert!(pool.threads_alive() > 0);
         assert!(pool.threads_waiting() > 0);
-        assert_eq!(*pool.as_builder().min_get(), Builder::min_default());
+        assert_eq!(pool.as_builder().min_get(), Builder::min_default());
         assert_eq!(
-            *pool.as_builder().max_get(),
+            pool.as_builder().max_get(),
             Builder::max_default()
         );
         assert_eq!(
             pool.as_builder().timeout_get(),
-            Some(&Duration::from_millis(TIME_OUT_MS))
+            Some(Duration::from_millis(TIME_OUT_MS))
         );
         assert!(pool.as_builder().name_get().is_none());
         assert!(pool.as_builder().stack_size_get().is_none());
         assert_eq!(
-            *pool.as_builder().load_limit_get(),
+            pool.as_builder().load_limit_get(),
             Builder::num_cpus() * Builder::num_cpus()
         );
 
@@ -377,18 +515,18 @@ mod tests {
     fn test(msg: usize, map: Arc<Mutex<Vec<(usize,usize)>>>) {
         let res = fib(msg);
         let mut maplock = map.lock().unwrap();
-        maplock[msg as usize].1 = res;
+        maplock[msg].1 = res;
     }
     fn fib(msg: usize) -> usize {
         match msg {
-            0...2 => 1,
+            0..=2 => 1,
             x => fib(x - 1) + fib(x - 2),
         }
     }
     #[test]
     fn scope_fib() {
         let pool = Pool::new().unwrap();
-        let mut array = (0..33usize).into_iter().map(|i| (i, 0)).collect::<Vec<_>>();
+        let mut array = (0..33usize).map(|i| (i, 0)).collect::<Vec<_>>();
 
         let mutex = Arc::new(Mutex::new(array.clone()));
         for i in 0..33usize {
@@ -410,7 +548,7 @@ mod tests {
     #[test]
     fn scope_x2() {
         let pool = Pool::new().unwrap();
-        let mut array = (0..100usize).into_iter().map(|i| (i, 0)).collect::<Vec<_>>();
+        let mut array = (0..100usize).map(|i| (i, 0)).collect::<Vec<_>>();
 
         let mutex = Arc::new(Mutex::new(array.clone()));
         for i in 0..100 {
@@ -434,4 +572,1049 @@ mod tests {
         let mut maplock = map.lock().unwrap();
         maplock[msg].1 = res;
     }
+    #[test]
+    fn priority() {
+        let pool = Builder::new().min(1).max(1).daemon(None).build().unwrap();
+        let (mp, mc) = std::sync::mpsc::channel::<()>();
+        pool.push(move || { let _ = mc.recv(); });
+        while pool.tasks_len() > 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for &p in &[0, 5, 2, 5] {
+            let order = order.clone();
+            pool.push_with_priority(p, move || order.lock().unwrap().push(p));
+        }
+        assert_eq!(pool.tasks_len(), 4);
+        assert_eq!(pool.tasks_lens().into_iter().collect::<Vec<_>>(), vec![(0, 1), (2, 1), (5, 2)]);
+
+        mp.send(()).unwrap();
+        pool.join();
+        assert_eq!(*order.lock().unwrap(), vec![5, 5, 2, 0]);
+    }
+    #[test]
+    fn priority_aging() {
+        let aging = Duration::from_millis(100);
+        let mut jobs = Lane::new(DEFAULT_QUEUE.to_owned());
+        let mut old = Job::new(Box::new(|| {}), 1);
+        old.pushed -= Duration::from_millis(350);
+        jobs.push(Job::new(Box::new(|| {}), 3));
+        jobs.push(old);
+
+        // 1 + 350/100 > 3
+        assert_eq!(jobs.pop(Some(&aging)).unwrap().priority, 1);
+        assert_eq!(jobs.pop(Some(&aging)).unwrap().priority, 3);
+        assert!(jobs.pop(Some(&aging)).is_none());
+        assert!(jobs.levels.is_empty());
+    }
+    #[test]
+    fn push_after() {
+        for pool in [Pool::new().unwrap(), Builder::new().daemon(None).build().unwrap()] {
+            let (mp, mc) = std::sync::mpsc::channel();
+            let st = Instant::now();
+            let handle = pool.push_after(Duration::from_millis(50), move || mp.send(Instant::now()).unwrap());
+            let ed = mc.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert!(ed.duration_since(st) >= Duration::from_millis(50));
+            assert!(handle.is_done());
+            assert!(!handle.cancel());
+        }
+    }
+    #[test]
+    fn push_at_cancel() {
+        let pool = Builder::new().daemon(None).build().unwrap();
+        let count = Arc::new(AtomicUsize::new(0));
+        let handles = (0..3)
+            .map(|i| {
+                let count = count.clone();
+                pool.push_at(Instant::now() + Duration::from_millis(50 * i), move || { count.fetch_add(1, Ordering::SeqCst); })
+            })
+            .collect::<Vec<_>>();
+        assert!(handles[2].cancel());
+        assert!(!handles[2].cancel());
+        thread::sleep(Duration::from_millis(200));
+        pool.join();
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        assert!(handles.iter().all(|h| h.is_done()));
+
+        // 取消的定时器及其闭包不会留到到期时。
+        let pool = Pool::new().unwrap();
+        let task = Arc::new(());
+        let handles = (0..8)
+            .map(|_| {
+                let task = task.clone();
+                pool.push_after(Duration::from_secs(3600), move || drop(task))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(pool.inner.as_builder().timers.len(), 8);
+        assert!(handles.iter().all(|h| h.cancel()));
+        assert_eq!(pool.inner.as_builder().timers.len(), 0);
+        assert_eq!(Arc::strong_count(&task), 1);
+
+        // 同一批里一个定时器 panic, 其余的照常执行。
+        for &daemon in &[Some(Duration::from_millis(TIME_OUT_MS)), None] {
+            let pool = Builder::new().daemon(daemon).build().unwrap();
+            let at = Instant::now() + Duration::from_millis(20);
+            pool.inner.schedule(at, Arc::default(), Box::new(|_: &Inner| panic!("timer")));
+            let (tx, rx) = std::sync::mpsc::channel();
+            pool.push_at(at, move || tx.send(()).unwrap());
+            rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        }
+    }
+    #[test]
+    fn schedule_fixed_rate() {
+        let pool = Builder::new().daemon(None).build().unwrap();
+        let count = Arc::new(AtomicUsize::new(0));
+        let count2 = count.clone();
+        let handle = pool.schedule_fixed_rate(Duration::from_millis(0), Duration::from_millis(20), move || {
+            count2.fetch_add(1, Ordering::SeqCst);
+        });
+        thread::sleep(Duration::from_millis(210));
+        assert!(handle.stop());
+        assert!(!handle.stop());
+        pool.join();
+        let runs = count.load(Ordering::SeqCst);
+        assert!((5..=12).contains(&runs), "runs: {}", runs);
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(count.load(Ordering::SeqCst), runs);
+    }
+    #[test]
+    fn schedule_fixed_rate_skip() {
+        let pool = Pool::new().unwrap();
+        let count = Arc::new(AtomicUsize::new(0));
+        let count2 = count.clone();
+        // 每次执行 100ms, 其间的触发都被跳过。
+        let handle = pool.schedule_fixed_rate(Duration::from_millis(0), Duration::from_millis(10), move || {
+            count2.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(100));
+        });
+        thread::sleep(Duration::from_millis(250));
+        handle.stop();
+        pool.join();
+        let runs = count.load(Ordering::SeqCst);
+        assert!((2..=3).contains(&runs), "runs: {}", runs);
+    }
+    #[test]
+    fn schedule_fixed_delay() {
+        let pool = Pool::new().unwrap();
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let runs2 = runs.clone();
+        let handle = pool.schedule_fixed_delay(Duration::from_millis(10), Duration::from_millis(50), move || {
+            runs2.lock().unwrap().push(Instant::now());
+            thread::sleep(Duration::from_millis(20));
+        });
+        thread::sleep(Duration::from_millis(300));
+        handle.stop();
+        pool.join();
+        let runs = runs.lock().unwrap();
+        assert!((2..=5).contains(&runs.len()), "runs: {}", runs.len());
+        for w in runs.windows(2) {
+            assert!(w[1].duration_since(w[0]) >= Duration::from_millis(70));
+        }
+    }
+    fn cron_next(expr: &str, secs: u64) -> Option<u64> {
+        let time = UNIX_EPOCH + Duration::from_secs(secs);
+        Cron::parse(expr).unwrap().next_after(time).map(|t| t.duration_since(UNIX_EPOCH).unwrap().as_secs())
+    }
+    #[test]
+    fn cron_next_after() {
+        // 2024-01-05 03:00:00 Fri -> 2024-01-08 02:30:00 Mon
+        assert_eq!(cron_next("0 30 2 * * MON-FRI", 1_704_423_600), Some(1_704_681_000));
+        assert_eq!(cron_next("30 2 * * 1-5", 1_704_423_600), Some(1_704_681_000));
+        // 2023-03-01 -> 2024-02-29 12:00:00
+        assert_eq!(cron_next("0 0 12 29 FEB ?", 1_677_628_800), Some(1_709_208_000));
+        // 2024-01-05 03:00:07 -> 03:00:15, 03:00:59 -> 03:01:00
+        assert_eq!(cron_next("*/15 * * * * *", 1_704_423_607), Some(1_704_423_615));
+        assert_eq!(cron_next("*/15 * * * * *", 1_704_423_659), Some(1_704_423_660));
+        // day-of-month or day-of-week: 2024-05-31 23:59:59 -> 2024-06-01 Sat -> 2024-06-03 Mon
+        assert_eq!(cron_next("0 0 0 1 * mon", 1_717_199_999), Some(1_717_200_000));
+        assert_eq!(cron_next("0 0 0 1 * mon", 1_717_200_000), Some(1_717_372_800));
+        // 1999-12-31 23:59:58 -> 23:59:59 -> 2000-12-31 23:59:59
+        assert_eq!(cron_next("59 59 23 31 DEC *", 946_684_798), Some(946_684_799));
+        assert_eq!(cron_next("59 59 23 31 DEC *", 946_684_799), Some(978_307_199));
+        assert_eq!(cron_next("0 0 0 30 2 *", 1_704_423_600), None);
+    }
+    #[test]
+    fn cron_parse_error() {
+        assert!(Cron::parse("0 0 0 1 * MON").is_ok());
+        assert!("5/15 10-40/10 1,2,3 * * *".parse::<Cron>().is_ok());
+        assert!("5/15 10-40/10 1,2,3 L * *".parse::<Cron>().is_err());
+        assert!(Cron::parse("61 * * * * *").is_err());
+        assert!(Cron::parse("* * *").is_err());
+        assert!(Cron::parse("0 0 0 * * FOO").is_err());
+        assert!(Cron::parse("0 0 5-1 * * *").is_err());
+        assert!(Cron::parse("*/0 * * * * *").is_err());
+        let e = Cron::parse("0 0 24 * * *").unwrap_err();
+        assert_eq!(e.to_string(), "invalid cron expression '0 0 24 * * *': hour '24' out of range 0-23");
+    }
+    #[test]
+    fn schedule_cron() {
+        let pool = Pool::new().unwrap();
+        assert!(pool.schedule_cron("0 0 0 30 2 *", || {}).unwrap().is_stopped());
+        assert!(pool.schedule_cron("0 0 0 * * FOO", || {}).is_err());
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let count2 = count.clone();
+        let handle = pool.schedule_cron("* * * * * *", move || { count2.fetch_add(1, Ordering::SeqCst); }).unwrap();
+        thread::sleep(Duration::from_millis(2500));
+        assert!(handle.stop());
+        pool.join();
+        let runs = count.load(Ordering::SeqCst);
+        assert!((2..=3).contains(&runs), "runs: {}", runs);
+    }
+    #[test]
+    fn cron_missed_fire() {
+        let pool = Pool::new().unwrap();
+        let late = SystemTime::now() - Duration::from_secs(10);
+        for &(missed_fire, runs) in &[(MissedFire::Skip, 0), (MissedFire::FireOnce, 1)] {
+            let count = Arc::new(AtomicUsize::new(0));
+            let count2 = count.clone();
+            let cron = Cron::parse("0 0 0 1 1 *").unwrap().missed_fire(missed_fire);
+            let (periodic, handle) = Periodic::new(move || { count2.fetch_add(1, Ordering::SeqCst); });
+            Periodic::cron(periodic, Arc::new(cron), late)(&pool.inner);
+            pool.join();
+            assert_eq!(count.load(Ordering::SeqCst), runs);
+            assert!(handle.stop());
+        }
+    }
+    #[test]
+    fn push_with_deadline() {
+        let late = Arc::new(Mutex::new(Vec::new()));
+        let late2 = late.clone();
+        let pool = Builder::new()
+            .min(1)
+            .max(1)
+            .on_expired(move |d| late2.lock().unwrap().push(d))
+            .build()
+            .unwrap();
+        let (mp, mc) = std::sync::mpsc::channel::<()>();
+        pool.push(move || { let _ = mc.recv(); });
+
+        let count = Arc::new(AtomicUsize::new(0));
+        for &ms in &[10, 10_000] {
+            let count = count.clone();
+            pool.push_with_deadline(Instant::now() + Duration::from_millis(ms), move || { count.fetch_add(1, Ordering::SeqCst); });
+        }
+        thread::sleep(Duration::from_millis(50));
+        mp.send(()).unwrap();
+        pool.join();
+
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(pool.tasks_expired(), 1);
+        let late = late.lock().unwrap();
+        assert_eq!(late.len(), 1);
+        assert!(late[0] >= Duration::from_millis(40));
+    }
+    #[test]
+    fn task_warn_after() {
+        for daemon in &[Some(Duration::from_millis(TIME_OUT_MS)), None] {
+            let warns = Arc::new(Mutex::new(Vec::new()));
+            let warns2 = warns.clone();
+            let pool = Builder::new()
+                .name("Worker")
+                .daemon(*daemon)
+                .task_warn_after(Some(Duration::from_millis(50)))
+                .on_task_warn(move |thread, task, elapsed| warns2.lock().unwrap().push((thread.to_owned(), task.map(|s| s.to_owned()), elapsed)))
+                .build()
+                .unwrap();
+            pool.push_named("slow", || thread::sleep(Duration::from_millis(200)));
+            pool.push(|| {});
+            pool.push(|| thread::sleep(Duration::from_millis(200)));
+            thread::sleep(Duration::from_millis(100));
+            pool.join();
+
+            let mut warns = warns.lock().unwrap();
+            warns.sort_by_key(|w| w.1.is_none());
+            assert_eq!(warns.len(), 2, "{:?}", *warns);
+            assert_eq!(warns[0].0, "Worker");
+            assert_eq!(warns[0].1, Some("slow".to_owned()));
+            assert_eq!(warns[1].1, None);
+            assert!(warns.iter().all(|w| w.2 >= Duration::from_millis(50)));
+        }
+
+        // 回调 panic 后 watchdog 继续检查。
+        let warns = Arc::new(AtomicUsize::new(0));
+        let warns2 = warns.clone();
+        let pool = Builder::new()
+            .task_warn_after(Some(Duration::from_millis(20)))
+            .on_task_warn(move |_, _, _| if warns2.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("on_task_warn");
+            })
+            .build()
+            .unwrap();
+        pool.push(|| thread::sleep(Duration::from_millis(60)));
+        thread::sleep(Duration::from_millis(100));
+        pool.push(|| thread::sleep(Duration::from_millis(60)));
+        pool.join();
+        assert_eq!(warns.load(Ordering::SeqCst), 2);
+    }
+    #[test]
+    fn work_stealing() {
+        let pool = Arc::new(Builder::new().min(2).max(2).scheduler(Scheduler::WorkStealing).build().unwrap());
+        let count = Arc::new(AtomicUsize::new(0));
+        let (mp, mc) = std::sync::mpsc::channel();
+
+        let pool2 = pool.clone();
+        let count2 = count.clone();
+        pool.push(move || {
+            for _ in 0..100 {
+                let count = count2.clone();
+                pool2.push(move || { count.fetch_add(1, Ordering::SeqCst); });
+            }
+            assert!(LOCAL.with(|l| l.borrow().is_some()));
+            // 本线程仍在忙, 其它线程偷走所有任务。
+            let st = Instant::now();
+            while count2.load(Ordering::SeqCst) < 100 && st.elapsed() < Duration::from_secs(5) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            mp.send(count2.load(Ordering::SeqCst)).unwrap();
+        });
+        assert_eq!(mc.recv().unwrap(), 100);
+        pool.join();
+        assert_eq!(pool.tasks_len(), 0);
+        assert!(LOCAL.with(|l| l.borrow().is_none()));
+    }
+    #[test]
+    fn push_keyed() {
+        let pool = Builder::new().min(4).max(4).build().unwrap();
+        let events = Arc::new(Mutex::new(BTreeMap::<&str, Vec<usize>>::new()));
+        let running = Arc::new(Mutex::new(Vec::<&str>::new()));
+        for i in 0..20 {
+            for &key in &["a", "b", "c"] {
+                let (events, running) = (events.clone(), running.clone());
+                pool.push_keyed(key, move || {
+                    {
+                        let mut running = running.lock().unwrap();
+                        assert!(!running.contains(&key), "{} runs concurrently", key);
+                        running.push(key);
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                    events.lock().unwrap().entry(key).or_default().push(i);
+                    running.lock().unwrap().retain(|k| *k != key);
+                });
+            }
+        }
+        // 下一个任务在上一个任务结束前入队。
+        pool.join();
+        let events = events.lock().unwrap();
+        for &key in &["a", "b", "c"] {
+            assert_eq!(events[key], (0..20).collect::<Vec<_>>());
+        }
+        assert!(pool.inner.as_builder().serials.lock().is_empty());
+    }
+    #[test]
+    fn weighted_queues() {
+        let mut jobs = Jobs::default();
+        let (a, b) = (jobs.lane("a"), jobs.lane("b"));
+        jobs.lanes[a].weight = 3;
+        for _ in 0..8 {
+            jobs.push(Job::new(Box::new(|| {}), 0).lane(a).name("a".to_owned()));
+            jobs.push(Job::new(Box::new(|| {}), 0).lane(b).name("b".to_owned()));
+        }
+        let order = (0..16).map(|_| jobs.pop(None).unwrap().name.unwrap()).collect::<String>();
+        assert_eq!(order, "aaabaaabaabbbbbb");
+        assert!(jobs.pop(None).is_none());
+        assert_eq!(jobs.len, 0);
+    }
+    #[test]
+    fn pool_queue() {
+        let pool = Builder::new().min(1).max(1).build().unwrap();
+        let (mp, mc) = std::sync::mpsc::channel::<()>();
+        pool.push(move || { let _ = mc.recv(); });
+        while pool.tasks_len() > 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let order = Arc::new(Mutex::new(String::new()));
+        let tenant = pool.queue("tenant-a").weight(2);
+        assert_eq!(tenant.weight_get(), 2);
+        assert_eq!(tenant.name(), "tenant-a");
+        for _ in 0..4 {
+            let order2 = order.clone();
+            tenant.push(move || order2.lock().unwrap().push('a'));
+            let order2 = order.clone();
+            pool.push(move || order2.lock().unwrap().push('d'));
+        }
+        assert_eq!(tenant.len(), 4);
+        assert_eq!(pool.queue(DEFAULT_QUEUE).len(), 4);
+        assert_eq!(pool.queues_len().into_iter().collect::<Vec<_>>(), vec![(DEFAULT_QUEUE.to_owned(), 4), ("tenant-a".to_owned(), 4)]);
+        assert_eq!(pool.tasks_len(), 8);
+
+        mp.send(()).unwrap();
+        pool.join();
+        // 阻塞任务用掉了 default 队列这一轮。
+        assert_eq!(*order.lock().unwrap(), "aadaaddd");
+        assert!(tenant.is_empty());
+    }
+    #[test]
+    fn push_batch() {
+        let pool = Builder::new().min(1).max(8).load_limit(10).daemon(None).build().unwrap();
+        while pool.threads_waiting() < 1 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        let count = Arc::new(AtomicUsize::new(0));
+        pool.push_batch((0..100).map(|_| {
+            let count = count.clone();
+            move || {
+                thread::sleep(Duration::from_millis(1));
+                count.fetch_add(1, Ordering::SeqCst);
+            }
+        }));
+        // 100 / (10 + 1) + 1 > 8
+        assert_eq!(pool.threads_future(), 8);
+        pool.join();
+        assert_eq!(count.load(Ordering::SeqCst), 100);
+
+        // 等待中的线程足够消化。
+        pool.push_batch((0..4).map(|_| || {}));
+        assert_eq!(pool.threads_future(), 8);
+        pool.push_batch(Vec::<fn()>::new());
+        pool.join();
+    }
+    #[derive(Clone, PartialEq, Eq)]
+    struct Collide(u32);
+    impl Hash for Collide {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            0.hash(state)
+        }
+    }
+    #[test]
+    fn push_unique() {
+        for &(coalesce, runs) in &[(Coalesce::KeepQueued, "a0b0"), (Coalesce::Replace, "a2b1")] {
+            let pool = Builder::new().min(1).max(1).coalesce(coalesce).build().unwrap();
+            let (mp, mc) = std::sync::mpsc::channel::<()>();
+            pool.push(move || { let _ = mc.recv(); });
+            while pool.tasks_len() > 0 {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            let order = Arc::new(Mutex::new(String::new()));
+            for &(key, i) in &[("a", 0), ("b", 0), ("a", 1), ("b", 1), ("a", 2)] {
+                let order = order.clone();
+                pool.push_unique(key, move || order.lock().unwrap().push_str(&format!("{}{}", key, i)));
+            }
+            assert_eq!(pool.tasks_len(), 2);
+            assert_eq!(pool.tasks_coalesced(), 3);
+
+            mp.send(()).unwrap();
+            pool.join();
+            assert_eq!(*order.lock().unwrap(), runs);
+
+            // 已开始执行的任务不再合并。
+            pool.push_unique("a", || {});
+            pool.join();
+            assert_eq!(pool.tasks_coalesced(), 3);
+            assert_eq!(pool.inner.as_builder().uniques.lock().len(), 0);
+        }
+
+        // 哈希值相同的不同 key 不合并。
+        let pool = Builder::new().min(1).max(1).build().unwrap();
+        let (mp, mc) = std::sync::mpsc::channel::<()>();
+        pool.push(move || { let _ = mc.recv(); });
+        let runs = Arc::new(AtomicUsize::new(0));
+        for i in 0..2 {
+            let runs = runs.clone();
+            pool.push_unique(Collide(i), move || { runs.fetch_add(1, Ordering::SeqCst); });
+        }
+        assert_eq!(pool.tasks_coalesced(), 0);
+        mp.send(()).unwrap();
+        pool.join();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+    #[test]
+    fn singleflight() {
+        let pool = Pool::new().unwrap();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let (mp, mc) = std::sync::mpsc::channel::<()>();
+        let mc = Arc::new(Mutex::new(mc));
+        let handles = (0..4)
+            .map(|_| {
+                let (runs, mc) = (runs.clone(), mc.clone());
+                pool.singleflight("answer", move || {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    let _ = mc.lock().unwrap().recv();
+                    42
+                })
+            })
+            .collect::<Vec<_>>();
+        assert!(handles.iter().all(|h| !h.is_done()));
+
+        mp.send(()).unwrap();
+        assert!(handles.iter().all(|h| h.wait() == Some(42)));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        pool.join();
+        assert_eq!(pool.inner.as_builder().flights.lock().len(), 0);
+
+        // 计算结束后, 同一 key 会再次执行; panic 时返回 None。
+        let handle = pool.singleflight("answer", || -> i32 { panic!("singleflight") });
+        assert_eq!(handle.wait(), None);
+        assert_eq!(handle.try_get(), Some(None));
+        assert_eq!(pool.singleflight("answer", || 43).wait(), Some(43));
+
+        // 任务未执行时 Pool 被 drop, 等待者得到 None。
+        let pool = Builder::new().min(1).max(1).build().unwrap();
+        pool.push(|| thread::sleep(Duration::from_millis(50)));
+        let handle = pool.singleflight("answer", || 44);
+        drop(pool);
+        assert_eq!(handle.wait(), None);
+
+        // 哈希值相同的不同 key 各自执行。
+        let pool = Pool::new().unwrap();
+        let a = pool.singleflight(Collide(1), || 1);
+        let b = pool.singleflight(Collide(2), || 2);
+        assert_eq!((a.wait(), b.wait()), (Some(1), Some(2)));
+    }
+    #[test]
+    fn run_graph() {
+        let pool = Pool::new().unwrap();
+        let outputs = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = TaskGraph::new();
+        let node = |graph: &mut TaskGraph, x: usize| {
+            let outputs = outputs.clone();
+            graph.add_node(move || outputs.lock().unwrap().push(x))
+        };
+        let (a, b) = (node(&mut graph, 1), node(&mut graph, 2));
+        let outputs_c = outputs.clone();
+        let c = graph.add_node(move || {
+            let mut outputs = outputs_c.lock().unwrap();
+            let sum = outputs.iter().sum();
+            outputs.push(sum);
+        });
+        let d = node(&mut graph, 4);
+        graph.depends_on(c, vec![a, b]).add_edge(c, d);
+        let report = pool.run_graph(graph).unwrap();
+        assert!(report.is_success());
+        assert_eq!(report.completed(), vec![a, b, c, d]);
+        assert_eq!(&outputs.lock().unwrap()[2..], &[3, 4]);
+
+        // panic 的节点的下游被跳过。
+        let mut graph = TaskGraph::new();
+        let a = graph.add_node(|| panic!("run_graph"));
+        let b = graph.add_node(|| {});
+        let c = graph.add_node(|| {});
+        let d = graph.add_node(|| {});
+        graph.add_edge(a, c).add_edge(b, c).add_edge(c, d);
+        let report = pool.run_graph(graph).unwrap();
+        assert_eq!((report.completed(), report.panicked(), report.skipped()), (vec![b], vec![a], vec![c, d]));
+
+        let mut graph = TaskGraph::new();
+        let a = graph.add_node(|| {});
+        let b = graph.add_node(|| {});
+        let c = graph.add_node(|| {});
+        graph.add_edge(a, b).add_edge(b, a).add_edge(a, c);
+        assert_eq!(pool.run_graph(graph).unwrap_err().cycle(), &[a, b, c]);
+        assert!(pool.run_graph(TaskGraph::new()).unwrap().is_success());
+
+        // 所有工作线程都在 run_graph() 里等待时不会死锁。
+        let pool = Arc::new(Builder::new().min(2).max(2).build().unwrap());
+        let (mp, mc) = std::sync::mpsc::channel();
+        for _ in 0..2 {
+            let (pool2, mp) = (pool.clone(), mp.clone());
+            pool.push(move || {
+                let mut graph = TaskGraph::new();
+                let (a, b, c) = (graph.add_node(|| {}), graph.add_node(|| {}), graph.add_node(|| {}));
+                graph.add_edge(a, b).add_edge(b, c);
+                mp.send(pool2.run_graph(graph).unwrap().is_success()).unwrap();
+            });
+        }
+        for _ in 0..2 {
+            assert!(mc.recv_timeout(Duration::from_secs(2)).unwrap());
+        }
+    }
+    #[derive(Clone)]
+    struct ManualClock {
+        start: Instant,
+        elapsed: Arc<Mutex<Duration>>,
+    }
+    impl ManualClock {
+        fn new() -> Self {
+            ManualClock { start: Instant::now(), elapsed: Arc::default() }
+        }
+        fn advance(&self, ms: u64) {
+            *self.elapsed.lock().unwrap() += Duration::from_millis(ms);
+        }
+    }
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.start + *self.elapsed.lock().unwrap()
+        }
+    }
+    #[test]
+    fn rate_limiter() {
+        let clock = ManualClock::new();
+        let limiter = RateLimiter::with_clock(10, 2, clock.clone());
+        assert_eq!(limiter.available(), 2);
+        assert!(limiter.try_acquire() && limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+        clock.advance(150);
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+        clock.advance(1000);
+        assert_eq!(limiter.available(), 2);
+
+        // 预约: 超出 burst 后按速率排队。
+        let waits = (0..4).map(|_| limiter.reserve().as_millis()).collect::<Vec<_>>();
+        assert_eq!(waits, vec![0, 0, 100, 200]);
+        clock.advance(100);
+        assert_eq!(limiter.reserve().as_millis(), 200);
+    }
+    #[test]
+    fn rate_limit() {
+        let pool = Builder::new().min(4).max(4).rate_limit(50, 5).build().unwrap();
+        let counter = Arc::new(AtomicUsize::new(0));
+        let start = Instant::now();
+        for _ in 0..15 {
+            let counter = counter.clone();
+            pool.push(move || { counter.fetch_add(1, Ordering::SeqCst); });
+        }
+        pool.join();
+        // 5 个立即开始, 其余 10 个每 20ms 一个。
+        assert_eq!(counter.load(Ordering::SeqCst), 15);
+        assert!(start.elapsed() >= Duration::from_millis(180));
+
+        // 在队列里等待的任务也按速率开始, 不会在线程空闲后一起开始。
+        for _ in 0..4 {
+            pool.push(|| thread::sleep(Duration::from_millis(100)));
+        }
+        let limiter = Arc::new(RateLimiter::new(50, 1));
+        let starts = Arc::new(Mutex::new(Vec::new()));
+        for _ in 0..6 {
+            let starts = starts.clone();
+            pool.push_rate_limited(&limiter, move || starts.lock().unwrap().push(Instant::now()));
+        }
+        thread::sleep(Duration::from_millis(150));
+        pool.join();
+        let mut starts = starts.lock().unwrap().clone();
+        starts.sort();
+        assert_eq!(starts.len(), 6);
+        assert!(starts.windows(2).all(|w| w[1] - w[0] >= Duration::from_millis(15)));
+
+        // 受限任务等待许可时不占用工作线程, 其它任务不受影响。
+        let pool = Builder::new().min(2).max(2).build().unwrap();
+        let limiter = Arc::new(RateLimiter::new(2, 1));
+        for _ in 0..10 {
+            pool.push_rate_limited(&limiter, || {});
+        }
+        let (mp, mc) = std::sync::mpsc::channel();
+        let st = Instant::now();
+        pool.push(move || mp.send(()).unwrap());
+        mc.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(st.elapsed() < Duration::from_millis(200));
+        assert!(pool.tasks_len() > 0 && !pool.is_empty());
+
+        // 等待许可期间超过截止时间的任务不执行。
+        let pool = Builder::new().min(1).max(1).rate_limit(10, 1).build().unwrap();
+        pool.push(|| {});
+        pool.push_with_deadline(Instant::now() + Duration::from_millis(50), || panic!("expired"));
+        pool.join();
+        assert_eq!(pool.tasks_expired(), 1);
+    }
+    #[test]
+    fn push_in() {
+        let pool = Builder::new().min(8).max(8).build().unwrap();
+        let disk = pool.category("disk").limit(2);
+        let (running, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let (mp, mc) = std::sync::mpsc::channel::<()>();
+        let mc = Arc::new(Mutex::new(mc));
+        for _ in 0..6 {
+            let (running, peak, mc) = (running.clone(), peak.clone(), mc.clone());
+            pool.push_in("disk", move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                let _ = mc.lock().unwrap().recv();
+                running.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+        // 超出限制的任务在类别中排队, 不进入 Pool。
+        assert_eq!((disk.running(), disk.len()), (2, 4));
+        assert!(pool.tasks_len() <= 2);
+
+        disk.limit(3);
+        assert_eq!(pool.category("disk").running(), 3);
+        drop(mp);
+        pool.join();
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        let disk = pool.category("disk");
+        assert_eq!((disk.limit_get(), disk.running(), disk.len()), (3, 0, 0));
+    }
+    #[test]
+    fn push_blocking() {
+        let pool = Builder::new().min(1).max(1).max_blocking(1).timeout(Some(Duration::from_millis(100))).build().unwrap();
+        let (mp, mc) = std::sync::mpsc::channel::<()>();
+        pool.push_blocking(move || { let _ = mc.recv(); });
+        while pool.threads_blocking() == 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        // 阻塞任务运行时, 其它任务在补偿线程上运行。
+        let (tx, rx) = std::sync::mpsc::channel();
+        pool.push(move || tx.send(()).unwrap());
+        rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(pool.threads_future(), 2);
+
+        mp.send(()).unwrap();
+        pool.join();
+        assert_eq!(pool.threads_blocking(), 0);
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(pool.threads_future(), 1);
+        assert_eq!(pool.threads_alive(), 1);
+    }
+    #[test]
+    fn scaling_policy() {
+        let state = PoolState {
+            min: 2,
+            max: 8,
+            load_limit: 4,
+            threads_future: 2,
+            threads_alive: 2,
+            threads_waiting: 0,
+            threads_blocking: 0,
+            tasks_len: 20,
+            queue_latency: None,
+            pushed: 1,
+        };
+        assert_eq!(LoadLimit.on_push(&state), Scaling::Spawn(1));
+        assert_eq!(LoadLimit.on_push(&PoolState { threads_waiting: 1, ..state }), Scaling::Keep);
+        assert_eq!(LoadLimit.on_push(&PoolState { tasks_len: 5, ..state }), Scaling::Keep);
+        assert_eq!(Eager.on_push(&PoolState { pushed: 3, ..state }), Scaling::Spawn(3));
+        assert_eq!(FixedSize.on_push(&state), Scaling::Keep);
+        assert_eq!(FixedSize.on_tick(&PoolState { threads_future: 1, ..state }), Scaling::Spawn(1));
+        assert_eq!(Eager.on_idle_timeout(&PoolState { threads_future: 3, ..state }), Scaling::Retire(1));
+
+        // 自定义策略: 每个 tick 退出一个多余的线程。
+        #[derive(Debug)]
+        struct Shrink;
+        impl ScalingPolicy for Shrink {
+            fn on_push(&self, state: &PoolState) -> Scaling {
+                Scaling::Spawn(state.pushed)
+            }
+            fn on_tick(&self, state: &PoolState) -> Scaling {
+                Scaling::Retire(state.threads_future.saturating_sub(state.min))
+            }
+        }
+        let pool = Builder::new().min(1).max(4).daemon(Some(Duration::from_millis(50))).scaling_policy(Shrink).build().unwrap();
+        for _ in 0..3 {
+            pool.push(|| thread::sleep(Duration::from_millis(10)));
+        }
+        assert_eq!(pool.state().threads_future, 4);
+        pool.join();
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(pool.threads_future(), 1);
+        assert_eq!(pool.threads_alive(), 1);
+    }
+    #[test]
+    fn target_queue_latency() {
+        let latency = Latency::default();
+        let now = Instant::now();
+        for ms in 1..=10 {
+            latency.record(now, Duration::from_millis(ms));
+        }
+        latency.refresh(now);
+        assert_eq!(latency.p90(), Some(Duration::from_millis(9)));
+        latency.refresh(now + Duration::from_millis(LATENCY_WINDOW_MS + 1));
+        assert_eq!(latency.p90(), None);
+
+        let policy = TargetLatency::new(Duration::from_millis(10));
+        let state = Builder::new().min(1).max(4).build().unwrap().state();
+        let state = PoolState { threads_future: 2, pushed: 1, ..state };
+        let slow = PoolState { queue_latency: Some(Duration::from_millis(20)), tasks_len: 3, ..state };
+        let fast = PoolState { queue_latency: Some(Duration::from_millis(1)), threads_waiting: 1, ..state };
+        assert_eq!(policy.on_push(&slow), Scaling::Spawn(1));
+        assert_eq!(policy.on_push(&fast), Scaling::Keep);
+        assert_eq!(policy.on_tick(&slow), Scaling::Spawn(1));
+        assert_eq!(policy.on_tick(&fast), Scaling::Retire(1));
+        assert_eq!(policy.on_idle_timeout(&PoolState { queue_latency: None, ..state }), Scaling::Retire(1));
+        assert_eq!(policy.on_idle_timeout(&slow), Scaling::Keep);
+
+        let pool = Builder::new()
+            .min(1)
+            .max(4)
+            .daemon(Some(Duration::from_millis(10)))
+            .target_queue_latency(Duration::from_millis(5))
+            .build()
+            .unwrap();
+        for _ in 0..20 {
+            pool.push(|| thread::sleep(Duration::from_millis(10)));
+        }
+        pool.join();
+        assert!(pool.threads_future() > 1);
+        assert!(pool.state().queue_latency.is_some());
+
+        // 默认的 LoadLimit 不读取, 也就不测量。
+        let pool = Pool::new().unwrap();
+        for _ in 0..20 {
+            pool.push(|| thread::sleep(Duration::from_millis(1)));
+        }
+        pool.join();
+        assert_eq!(pool.state().queue_latency, None);
+    }
+    #[test]
+    fn set_min_max() {
+        let pool = Builder::new().min(1).max(2).timeout(None).build().unwrap();
+        assert_eq!(pool.set_min(3), Err(LimitError::new(3, 2)));
+        assert_eq!(pool.set_max(0), Err(LimitError::new(1, 0)));
+        pool.set_max(4).unwrap();
+        pool.set_min(4).unwrap();
+        assert_eq!(pool.set_max(3), Err(LimitError::new(4, 3)));
+        assert_eq!(pool.threads_future(), 4);
+        while pool.threads_waiting() < 4 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // 减小 max 后, 多余的空闲线程立即退出。
+        pool.set_min(1).unwrap();
+        pool.set_max(1).unwrap();
+        while pool.threads_alive() > 1 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(pool.threads_future(), 1);
+
+        pool.set_timeout(Some(Duration::from_millis(50)));
+        pool.set_load_limit(8);
+        assert_eq!(pool.as_builder().timeout_get(), Some(Duration::from_millis(50)));
+        assert_eq!(pool.as_builder().load_limit_get(), 8);
+        assert_eq!((pool.as_builder().min_get(), pool.as_builder().max_get()), (1, 1));
+
+        // 线程都在忙时, 重复减小 max 只退出一次多余的线程。
+        let pool = Builder::new().min(8).max(8).timeout(None).build().unwrap();
+        pool.set_min(1).unwrap();
+        let (gate, started) = (Arc::new(Mutex::new(())), Arc::new(AtomicUsize::new(0)));
+        let guard = gate.lock().unwrap();
+        for _ in 0..8 {
+            let (gate, started) = (gate.clone(), started.clone());
+            pool.push(move || {
+                started.fetch_add(1, Ordering::SeqCst);
+                drop(gate.lock().unwrap());
+            });
+        }
+        while started.load(Ordering::SeqCst) < 8 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        pool.set_max(4).unwrap();
+        pool.set_max(4).unwrap();
+        drop(guard);
+        pool.join();
+        while pool.threads_alive() > 4 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!((pool.threads_future(), pool.threads_alive()), (4, 4));
+
+        // 线程都在忙时减小 max 再增大, 待退出的线程随之取消。
+        pool.set_max(8).unwrap();
+        pool.add_threads(4).unwrap();
+        let guard = gate.lock().unwrap();
+        started.store(0, Ordering::SeqCst);
+        for _ in 0..8 {
+            let (gate, started) = (gate.clone(), started.clone());
+            pool.push(move || {
+                started.fetch_add(1, Ordering::SeqCst);
+                drop(gate.lock().unwrap());
+            });
+        }
+        while started.load(Ordering::SeqCst) < 8 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        pool.set_max(2).unwrap();
+        pool.set_max(8).unwrap();
+        drop(guard);
+        pool.join();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!((pool.threads_future(), pool.threads_alive()), (8, 8));
+    }
+    #[test]
+    fn remove_threads() {
+        let pool = Builder::new().min(1).max(4).timeout(None).build().unwrap();
+        pool.add_threads(3).unwrap();
+        while pool.threads_waiting() < 4 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        // 正在运行任务的线程不会退出。
+        let (mp, mc) = std::sync::mpsc::channel::<()>();
+        pool.push(move || { let _ = mc.recv(); });
+        while pool.threads_waiting() > 3 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(pool.remove_threads(10), 3);
+        assert_eq!(pool.remove_threads(10), 0);
+        while pool.threads_alive() > 1 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(pool.threads_future(), 1);
+
+        mp.send(()).unwrap();
+        pool.join();
+        assert_eq!(pool.remove_threads(1), 0);
+        assert_eq!((pool.threads_future(), pool.threads_alive()), (1, 1));
+    }
+    #[test]
+    fn idle_reaping() {
+        let pool = Builder::new().min(2).max(6).scaling_policy(Eager).timeout(Some(Duration::from_millis(50))).daemon(None).build().unwrap();
+        let (mp, mc) = std::sync::mpsc::channel::<()>();
+        let mc = Arc::new(Mutex::new(mc));
+        for _ in 0..6 {
+            let mc = mc.clone();
+            pool.push(move || { let _ = mc.lock().unwrap().recv(); });
+            while pool.tasks_len() > 0 {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        assert_eq!(pool.threads_future(), 6);
+        drop(mp);
+        pool.join();
+
+        // 超出 min 的线程空闲 timeout 后退出。
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!((pool.threads_future(), pool.threads_alive()), (2, 2));
+
+        // panic 的线程同样计数准确。
+        pool.push(|| panic!("idle_reaping"));
+        while pool.threads_alive() > 1 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(pool.threads_future(), 1);
+        let (tx, rx) = std::sync::mpsc::channel();
+        pool.push(move || tx.send(()).unwrap());
+        rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    }
+    #[test]
+    fn scale_from_zero() {
+        let pool = Builder::new().min(0).max(2).timeout(Some(Duration::from_millis(20))).build().unwrap();
+        assert_eq!((pool.threads_future(), pool.threads_alive()), (0, 0));
+        for i in 0..3 {
+            let (tx, rx) = std::sync::mpsc::channel();
+            pool.push(move || tx.send(()).unwrap());
+            rx.recv_timeout(Duration::from_secs(1)).unwrap();
+            pool.join();
+            // 空闲 timeout 后回到零线程。
+            thread::sleep(Duration::from_millis(100 * i));
+        }
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!((pool.threads_future(), pool.threads_alive()), (0, 0));
+
+        // 线程退出与新任务同时发生时, 任务也不会滞留。
+        let pool = Builder::new().min(0).max(1).timeout(Some(Duration::from_millis(1))).build().unwrap();
+        for i in 0..200 {
+            let (tx, rx) = std::sync::mpsc::channel();
+            pool.push(move || tx.send(()).unwrap());
+            rx.recv_timeout(Duration::from_secs(1)).unwrap();
+            thread::sleep(Duration::from_micros(i % 20 * 100));
+        }
+    }
+    #[test]
+    fn daemon_supervisor() {
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let (panic, daemon) = (Arc::new(AtomicBool::new(false)), Arc::new(Mutex::new(None)));
+        let (ticks2, panic2, daemon2) = (ticks.clone(), panic.clone(), daemon.clone());
+        let pool = Builder::new()
+            .min(1)
+            .max(1)
+            .daemon(Some(Duration::from_millis(10)))
+            .on_daemon_tick(move |state| {
+                assert!(state.max >= 1);
+                *daemon2.lock().unwrap() = Some(thread::current().id());
+                ticks2.fetch_add(1, Ordering::SeqCst);
+                if panic2.swap(false, Ordering::SeqCst) {
+                    panic!("daemon_supervisor");
+                }
+            })
+            .build()
+            .unwrap();
+        let wait_for = |f: &dyn Fn() -> bool| {
+            let start = Instant::now();
+            while !f() {
+                assert!(start.elapsed() < Duration::from_secs(2));
+                thread::sleep(Duration::from_millis(5));
+            }
+        };
+        wait_for(&|| ticks.load(Ordering::SeqCst) > 0);
+
+        // 计数漂移被修正。
+        pool.inner.as_builder().threads_future.fetch_add(3, Ordering::SeqCst);
+        wait_for(&|| pool.threads_future() == 1 && pool.threads_alive() == 1);
+
+        // daemon 线程 panic 后由新的线程接替, 定时器照常触发。
+        let before = daemon.lock().unwrap().unwrap();
+        panic.store(true, Ordering::SeqCst);
+        wait_for(&|| !panic.load(Ordering::SeqCst));
+        let now = ticks.load(Ordering::SeqCst);
+        wait_for(&|| ticks.load(Ordering::SeqCst) > now + 2);
+        assert_ne!(daemon.lock().unwrap().unwrap(), before);
+        assert!(pool.daemon_alive());
+        let (mp, mc) = std::sync::mpsc::channel();
+        pool.push_after(Duration::from_millis(20), move || mp.send(()).unwrap());
+        mc.recv_timeout(Duration::from_secs(1)).unwrap();
+    }
+    #[test]
+    fn spawn_error() {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors2 = errors.clone();
+        // 过大的栈无法分配, 新建线程失败。
+        let pool = Builder::new()
+            .min(0)
+            .max(2)
+            .stack_size(1 << 60)
+            .spawn_retry(2, Duration::from_millis(10))
+            .on_spawn_error(move |_, attempt| errors2.lock().unwrap().push(attempt))
+            .build()
+            .unwrap();
+        let caller = thread::current().id();
+        let (tx, rx) = std::sync::mpsc::channel();
+        for _ in 0..3 {
+            let tx = tx.clone();
+            pool.push(move || tx.send(thread::current().id()).unwrap());
+            assert_eq!(rx.try_recv().unwrap(), caller);
+        }
+        assert_eq!(pool.threads_future(), 0);
+
+        // 只有一个重试在等待。
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(*errors.lock().unwrap(), vec![0, 0, 0, 1, 2]);
+        assert_eq!((pool.threads_future(), pool.threads_alive()), (0, 0));
+        // Pool::add_threads() 的错误不重试。
+        assert!(pool.add_threads(1).is_err());
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(errors.lock().unwrap().len(), 6);
+
+        // 过期的任务不执行, 定时器添加的任务不在 daemon 线程上执行, 留在队列里。
+        let pool = Builder::new().min(0).max(2).stack_size(1 << 60).spawn_retry(0, Duration::from_millis(10)).build().unwrap();
+        pool.push_with_deadline(Instant::now(), || panic!("expired"));
+        assert_eq!(pool.tasks_expired(), 1);
+        let (tx, rx) = std::sync::mpsc::channel();
+        pool.push_after(Duration::from_millis(1), move || tx.send(()).unwrap());
+        let start = Instant::now();
+        while pool.tasks_len() == 0 {
+            assert!(start.elapsed() < Duration::from_secs(1));
+            thread::sleep(Duration::from_millis(1));
+        }
+        let ran = Arc::new(AtomicBool::new(false));
+        let ran2 = ran.clone();
+        pool.push(move || ran2.store(true, Ordering::SeqCst));
+        assert!(ran.load(Ordering::SeqCst));
+        assert_eq!(pool.tasks_len(), 1);
+        assert!(rx.try_recv().is_err());
+    }
+    #[test]
+    fn prestart() {
+        let pool = Builder::new().min(3).max(4).build().unwrap();
+        assert_eq!(*pool.as_builder().prestart_get(), Prestart::Eager);
+        assert_eq!(pool.threads_future(), 3);
+
+        let pool = Builder::new().min(3).max(4).daemon(Some(Duration::from_millis(10))).prestart(Prestart::Lazy).build().unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(pool.threads_future(), 0);
+        let (tx, rx) = std::sync::mpsc::channel();
+        pool.push(move || tx.send(()).unwrap());
+        rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(pool.threads_future(), 3);
+
+        for &daemon in &[Some(Duration::from_millis(10)), None] {
+            let pool = Builder::new().min(3).max(4).daemon(daemon).prestart(Prestart::Background).build().unwrap();
+            let start = Instant::now();
+            while pool.threads_alive() < 3 {
+                assert!(start.elapsed() < Duration::from_secs(1));
+                thread::sleep(Duration::from_millis(1));
+            }
+            assert_eq!(pool.threads_future(), 3);
+        }
+    }
 }
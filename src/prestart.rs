@@ -0,0 +1,42 @@
+/// When the `Pool` spawns its `min` threads, see `Builder::prestart()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Prestart {
+    /// Spawns them before `Builder::build()` returns, the default.
+    #[default]
+    Eager,
+    /// Spawns them on the first push.
+    Lazy,
+    /// Spawns them from the daemon thread(or the timer thread if the daemon is closed) after `Builder::build()` returned.
+    Background,
+}
+
+impl Inner {
+    fn prestart(&self) -> io::Result<()> {
+        match *self.as_builder().prestart_get() {
+            Prestart::Eager => self.start().map_err(|e| e.1),
+            Prestart::Lazy => Ok(()),
+            Prestart::Background => {
+                self.schedule(
+                    Instant::now(),
+                    Arc::default(),
+                    Box::new(|inner: &Inner| {
+                        let _ = inner.start();
+                    }),
+                );
+                Ok(())
+            }
+        }
+    }
+    #[inline]
+    fn started(&self) -> bool {
+        self.as_builder().started.load(Ordering::SeqCst)
+    }
+    // 只执行一次: 补足 min 个线程。
+    fn start(&self) -> Result<(), (usize, io::Error)> {
+        if self.as_builder().started.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let min = self.as_builder().min_get();
+        self.grow(min.saturating_sub(self.threads_future()))
+    }
+}
@@ -0,0 +1,443 @@
+/// Defaults interval(ms) that raises a waiting task's priority by one.
+const AGING_MS: u64 = 1_000;
+
+// 空队列时睡眠前让出 CPU 的次数。
+const SPINS: usize = 4;
+
+/// The name of the queue used by `Pool::push()`.
+pub const DEFAULT_QUEUE: &str = "default";
+
+/// The priority of a `Task`, the larger one runs first.
+///
+/// `Pool::push()` uses `0`, the lowest priority.
+pub type Priority = u8;
+
+// 队列中的任务及其元数据。
+struct Job {
+    task: Task,
+    priority: Priority,
+    pushed: Instant,
+    deadline: Option<Instant>,
+    name: Option<String>,
+    lane: usize,
+    // 开始前要取得的许可, 见 Pool::push_rate_limited()。
+    limiter: Option<Arc<RateLimiter>>,
+    // 入队的序号, 见 Queue::remove()。
+    seq: u64,
+}
+
+impl Job {
+    #[inline]
+    fn new(task: Task, priority: Priority) -> Self {
+        Job {
+            task,
+            priority,
+            pushed: Instant::now(),
+            deadline: None,
+            name: None,
+            lane: 0,
+            limiter: None,
+            seq: 0,
+        }
+    }
+    #[inline]
+    fn lane(mut self, lane: usize) -> Self {
+        self.lane = lane;
+        self
+    }
+    #[inline]
+    fn name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+    #[inline]
+    fn limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+    #[inline]
+    fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+    // 返回已超过截止时间多久。
+    #[inline]
+    fn expired(&self, now: Instant) -> Option<Duration> {
+        self.deadline.and_then(|d| if now > d { Some(now - d) } else { None })
+    }
+    // 等待时间越长, 优先级越高。
+    #[inline]
+    fn aged_priority(&self, now: Instant, aging: Option<&Duration>) -> u64 {
+        let bonus = match aging {
+            Some(aging) if *aging > Duration::from_millis(0) => (now.duration_since(self.pushed).as_nanos() / aging.as_nanos()) as u64,
+            _ => 0,
+        };
+        self.priority as u64 + bonus
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PopError {
+    Timeout,
+    Closed,
+    // 其它工作线程的本地队列里有任务可偷。
+    Steal,
+    // ScalingPolicy 要求减少线程。
+    Retire,
+}
+
+// The tasks_queue: the named queues take turns by weight, FIFO inside the same priority.
+struct Queue {
+    jobs: Mutex<Jobs>,
+    cond: Condvar,
+    // 所有工作线程本地队列里的任务数目。
+    locals: AtomicUsize,
+    // Jobs::len 的副本, 读取时无需加锁。
+    len: AtomicUsize,
+    // 等待自己的许可而暂离队列的任务数, 见 Inner::park()。
+    parked: AtomicUsize,
+}
+
+struct Jobs {
+    lanes: Vec<Lane>,
+    names: HashMap<String, usize>,
+    // 轮到的队列。
+    cursor: usize,
+    len: usize,
+    // 待退出的空闲线程数。
+    retire: usize,
+    // 阻塞在 Condvar 上的线程数。
+    sleepers: usize,
+    // 已唤醒但尚未返回的线程数, 它们返回后会先检查队列, 入队时无需再唤醒。
+    woken: usize,
+    seq: u64,
+    closed: bool,
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        let mut jobs = Jobs {
+            lanes: Vec::new(),
+            names: HashMap::new(),
+            cursor: 0,
+            len: 0,
+            retire: 0,
+            sleepers: 0,
+            woken: 0,
+            seq: 0,
+            closed: false,
+        };
+        jobs.lane(DEFAULT_QUEUE);
+        jobs
+    }
+}
+
+impl Jobs {
+    fn lane(&mut self, name: &str) -> usize {
+        if let Some(idx) = self.names.get(name) {
+            return *idx;
+        }
+        let idx = self.lanes.len();
+        self.lanes.push(Lane::new(name.to_owned()));
+        self.names.insert(name.to_owned(), idx);
+        idx
+    }
+    fn push(&mut self, mut job: Job) -> u64 {
+        job.seq = self.seq;
+        self.seq += 1;
+        self.lanes[job.lane].push(job);
+        self.len += 1;
+        self.seq - 1
+    }
+    // 新增 num 个任务后是否需要唤醒(num 为 1 时唤醒一个, 否则全部唤醒)。
+    fn wake(&mut self, num: usize) -> bool {
+        let idle = self.sleepers.saturating_sub(self.woken);
+        if num == 0 || idle == 0 {
+            return false;
+        }
+        self.woken += if num == 1 { 1 } else { idle };
+        true
+    }
+    // 取出序号在 seqs 内且仍在排队的任务。
+    fn remove(&mut self, seqs: Range<u64>) -> Vec<Job> {
+        let mut removed = Vec::new();
+        for lane in &mut self.lanes {
+            for jobs in lane.levels.values_mut() {
+                let mut idx = 0;
+                while idx < jobs.len() {
+                    if seqs.contains(&jobs[idx].seq) {
+                        removed.extend(jobs.remove(idx));
+                        lane.len -= 1;
+                    } else {
+                        idx += 1;
+                    }
+                }
+            }
+            lane.levels.retain(|_, jobs| !jobs.is_empty());
+        }
+        self.len -= removed.len();
+        removed.sort_by_key(|job| job.seq);
+        removed
+    }
+    // 加权轮询(deficit round-robin, 每个任务的代价为 1)。
+    fn pop(&mut self, aging: Option<&Duration>) -> Option<Job> {
+        if self.len == 0 {
+            return None;
+        }
+        let lanes = self.lanes.len();
+        for _ in 0..lanes {
+            let idx = self.cursor % lanes;
+            let lane = &mut self.lanes[idx];
+            if lane.len == 0 {
+                lane.deficit = 0;
+                self.cursor = idx + 1;
+                continue;
+            }
+            if lane.deficit == 0 {
+                lane.deficit = lane.weight;
+            }
+            let job = lane.pop(aging);
+            lane.deficit -= 1;
+            if lane.deficit == 0 || lane.len == 0 {
+                lane.deficit = 0;
+                self.cursor = idx + 1;
+            }
+            self.len -= 1;
+            return job;
+        }
+        None
+    }
+    fn lens(&self) -> BTreeMap<Priority, usize> {
+        let mut lens = BTreeMap::new();
+        for (p, jobs) in self.lanes.iter().flat_map(|l| l.levels.iter()) {
+            *lens.entry(*p).or_insert(0) += jobs.len();
+        }
+        lens
+    }
+}
+
+// 一个命名队列。
+struct Lane {
+    name: String,
+    weight: usize,
+    deficit: usize,
+    levels: BTreeMap<Priority, VecDeque<Job>>,
+    len: usize,
+}
+
+impl Lane {
+    fn new(name: String) -> Self {
+        Lane {
+            name,
+            weight: 1,
+            deficit: 0,
+            levels: BTreeMap::new(),
+            len: 0,
+        }
+    }
+    fn push(&mut self, job: Job) {
+        self.levels.entry(job.priority).or_default().push_back(job);
+        self.len += 1;
+    }
+    // 选出(老化后)优先级最高的队首, 同等优先级先进先出。
+    fn pop(&mut self, aging: Option<&Duration>) -> Option<Job> {
+        // 只有一个优先级时无需比较。
+        let priority = if self.levels.len() == 1 {
+            *self.levels.keys().next()?
+        } else {
+            let now = Instant::now();
+            let mut best: Option<(Priority, u64, Instant)> = None;
+            for (priority, jobs) in &self.levels {
+                if let Some(job) = jobs.front() {
+                    let aged = job.aged_priority(now, aging);
+                    let better = match best {
+                        None => true,
+                        Some((_, a, p)) => aged > a || aged == a && job.pushed < p,
+                    };
+                    if better {
+                        best = Some((*priority, aged, job.pushed));
+                    }
+                }
+            }
+            best?.0
+        };
+        let (job, empty) = {
+            let jobs = self.levels.get_mut(&priority).unwrap();
+            (jobs.pop_front(), jobs.is_empty())
+        };
+        if empty {
+            self.levels.remove(&priority);
+        }
+        self.len -= 1;
+        job
+    }
+}
+
+impl Queue {
+    fn new() -> Self {
+        Queue {
+            jobs: Mutex::new(Jobs::default()),
+            cond: Condvar::new(),
+            locals: AtomicUsize::default(),
+            len: AtomicUsize::default(),
+            parked: AtomicUsize::default(),
+        }
+    }
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Jobs> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    // 返回任务的序号。
+    fn push(&self, job: Job) -> u64 {
+        let (seq, wake) = {
+            let mut jobs = self.lock();
+            let seq = jobs.push(job);
+            self.len.store(jobs.len, Ordering::SeqCst);
+            (seq, jobs.wake(1))
+        };
+        if wake {
+            self.cond.notify_one();
+        }
+        seq
+    }
+    // 返回这批任务的序号。
+    fn push_batch(&self, batch: Vec<Job>) -> Range<u64> {
+        let len = batch.len();
+        let (seqs, wake) = {
+            let mut jobs = self.lock();
+            let start = jobs.seq;
+            for job in batch {
+                jobs.push(job);
+            }
+            self.len.store(jobs.len, Ordering::SeqCst);
+            (start..jobs.seq, jobs.wake(len))
+        };
+        match len {
+            _ if !wake => {}
+            1 => self.cond.notify_one(),
+            _ => self.cond.notify_all(),
+        }
+        seqs
+    }
+    /// Blocks until a `Job` arrives, the timeout elapses or the queue is closed.
+    ///
+    /// `waiting` is maintained under the lock, so `is_idle()` never misses a `Job` just popped,
+    /// the timeout is read again after each wakeup, see `Queue::wake()`.
+    fn pop<F>(&self, timeout: F, aging: Option<&Duration>, waiting: &AtomicUsize) -> Result<Job, PopError>
+    where
+        F: Fn() -> Option<Duration>,
+    {
+        let start = Instant::now();
+        let mut spins = 0;
+        // 立即取到任务的线程不计入 waiting。
+        let mut counted = false;
+        let mut jobs = self.lock();
+        let result = loop {
+            if jobs.closed {
+                break Err(PopError::Closed);
+            }
+            if let Some(job) = jobs.pop(aging) {
+                self.len.store(jobs.len, Ordering::SeqCst);
+                break Ok(job);
+            }
+            if self.locals.load(Ordering::SeqCst) > 0 {
+                break Err(PopError::Steal);
+            }
+            if jobs.retire > 0 {
+                jobs.retire -= 1;
+                break Err(PopError::Retire);
+            }
+            if !counted {
+                counted = true;
+                waiting.fetch_add(1, Ordering::SeqCst);
+            }
+            // 先让出几次 CPU, 入队频繁时不必睡眠和唤醒。
+            if spins < SPINS {
+                spins += 1;
+                drop(jobs);
+                thread::yield_now();
+                jobs = self.lock();
+                continue;
+            }
+            let deadline = timeout().map(|t| start + t);
+            let now = Instant::now();
+            if deadline.is_some_and(|d| now >= d) {
+                break Err(PopError::Timeout);
+            }
+            jobs.sleepers += 1;
+            jobs = match deadline {
+                Some(deadline) => self.cond.wait_timeout(jobs, deadline - now).unwrap_or_else(|e| e.into_inner()).0,
+                None => self.cond.wait(jobs).unwrap_or_else(|e| e.into_inner()),
+            };
+            jobs.sleepers -= 1;
+            jobs.woken = jobs.woken.saturating_sub(1);
+        };
+        if counted {
+            waiting.fetch_sub(1, Ordering::SeqCst);
+        }
+        result
+    }
+    // 取回尚未被取走的任务。
+    fn remove(&self, seqs: Range<u64>) -> Vec<Job> {
+        let mut jobs = self.lock();
+        let removed = jobs.remove(seqs);
+        self.len.store(jobs.len, Ordering::SeqCst);
+        removed
+    }
+    // 本地队列添加任务(locals 已计数)后, 有工作线程在等待时唤醒一个去偷任务。
+    fn push_local(&self, waiting: &AtomicUsize) {
+        if waiting.load(Ordering::SeqCst) > 0 {
+            drop(self.lock());
+            self.cond.notify_one();
+        }
+    }
+    // 唤醒等待中的线程, 以重新读取 timeout。
+    fn wake(&self) {
+        drop(self.lock());
+        self.cond.notify_all();
+    }
+    // 唤醒等待中的线程, 其中 f(已待退出的数目) 个退出, 返回该数目。
+    fn retire<F>(&self, f: F) -> usize
+    where
+        F: FnOnce(usize) -> usize,
+    {
+        let num = {
+            let mut jobs = self.lock();
+            let num = f(jobs.retire);
+            jobs.retire += num;
+            num
+        };
+        if num > 0 {
+            self.cond.notify_all();
+        }
+        num
+    }
+    fn close(&self) {
+        self.lock().closed = true;
+        self.cond.notify_all();
+    }
+    #[inline]
+    fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+    // All threads are waiting and the queue's length is 0.
+    fn is_idle(&self, alive: &AtomicUsize, waiting: &AtomicUsize) -> bool {
+        let jobs = self.lock();
+        jobs.len == 0
+            && self.locals.load(Ordering::SeqCst) == 0
+            && self.parked.load(Ordering::SeqCst) == 0
+            && waiting.load(Ordering::SeqCst) == alive.load(Ordering::SeqCst)
+    }
+    fn lens(&self) -> BTreeMap<Priority, usize> {
+        self.lock().lens()
+    }
+    fn lanes_len(&self) -> BTreeMap<String, usize> {
+        self.lock().lanes.iter().map(|l| (l.name.clone(), l.len)).collect()
+    }
+}
+
+impl Debug for Queue {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_map().entries(self.lanes_len()).finish()
+    }
+}
@@ -0,0 +1,166 @@
+/// The source of time of the `RateLimiter`, it can be replaced to test deterministically.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The `Clock` uses `Instant::now()`, the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// 令牌数(预约后可以为负)及其更新时间。
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+/// A token bucket: up to `burst` permits at once, refilled by `permits_per_sec`,
+///
+/// used by `Builder::rate_limit()` and `Pool::push_rate_limited()`(shared in an `Arc`).
+pub struct RateLimiter {
+    permits_per_sec: u32,
+    burst: u32,
+    bucket: Mutex<Bucket>,
+    clock: Box<dyn Clock>,
+}
+
+impl Debug for RateLimiter {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("RateLimiter")
+            .field("permits_per_sec", &self.permits_per_sec)
+            .field("burst", &self.burst)
+            .field("bucket", &*self.lock())
+            .finish()
+    }
+}
+
+impl RateLimiter {
+    /// The bucket starts full.
+    pub fn new(permits_per_sec: u32, burst: u32) -> Self {
+        Self::with_clock(permits_per_sec, burst, SystemClock)
+    }
+    pub fn with_clock<C>(permits_per_sec: u32, burst: u32, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        assert!(permits_per_sec != 0, "permits_per_sec == 0");
+        let burst = cmp::max(burst, 1);
+        let bucket = Bucket {
+            tokens: burst as f64,
+            last: clock.now(),
+        };
+        RateLimiter {
+            permits_per_sec,
+            burst,
+            bucket: Mutex::new(bucket),
+            clock: Box::new(clock),
+        }
+    }
+    #[inline]
+    pub fn permits_per_sec(&self) -> u32 {
+        self.permits_per_sec
+    }
+    #[inline]
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Bucket> {
+        self.bucket.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    fn refill(&self) -> MutexGuard<'_, Bucket> {
+        let mut bucket = self.lock();
+        let now = self.clock.now();
+        if now > bucket.last {
+            let elapsed = now.duration_since(bucket.last);
+            let tokens = bucket.tokens + elapsed.as_secs_f64() * self.permits_per_sec as f64;
+            bucket.tokens = tokens.min(self.burst as f64);
+            bucket.last = now;
+        }
+        bucket
+    }
+    /// Takes a permit if one is available.
+    pub fn try_acquire(&self) -> bool {
+        let mut bucket = self.refill();
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+    /// Reserves the next permit, returns how long to wait before using it.
+    pub fn reserve(&self) -> Duration {
+        let mut bucket = self.refill();
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.permits_per_sec as f64)
+        }
+    }
+    /// Returns the number of permits available now.
+    pub fn available(&self) -> u32 {
+        cmp::max(self.refill().tokens as i64, 0) as u32
+    }
+}
+
+// 暂离队列的任务重新入队(或被丢弃)后计数减一。
+struct ParkGuard {
+    pool: Weak<Builder>,
+}
+
+impl Drop for ParkGuard {
+    fn drop(&mut self) {
+        if let Some(inner) = Inner::upgrade(&self.pool) {
+            inner.as_builder().queue.parked.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Inner {
+    // 预约任务自己的许可, 需要等待时由定时器到期后重新入队, 返回 None; 工作线程继续取其它任务。
+    fn park(&self, mut job: Job) -> Option<Job> {
+        let wait = match job.limiter.take() {
+            Some(limiter) => limiter.reserve(),
+            None => return Some(job),
+        };
+        if wait == Duration::from_secs(0) {
+            return Some(job);
+        }
+        self.as_builder().queue.parked.fetch_add(1, Ordering::SeqCst);
+        let guard = ParkGuard { pool: self.downgrade() };
+        self.schedule(
+            Instant::now() + wait,
+            Arc::default(),
+            Box::new(move |inner: &Inner| {
+                // 先入队再减计数, is_empty() 不会误判。
+                inner.push_job(job);
+                drop(guard);
+            }),
+        );
+        None
+    }
+}
+
+impl Pool {
+    /// Appends a task to the Pool, the worker takes a permit from the `RateLimiter` before it starts the `Task`,
+    ///
+    /// share the `RateLimiter` between the `Task`s of a class to limit how fast they start regardless of the threads.
+    ///
+    /// If the permit isn't available yet the worker reserves it and moves on to other `Task`s,
+    /// the `Task` is queued again when its permit is due.
+    pub fn push_rate_limited<T>(&self, limiter: &Arc<RateLimiter>, task: T)
+    where
+        T: Runable + Send + 'static,
+    {
+        self.inner.push_job(Job::new(Box::new(task), 0).limiter(limiter.clone()))
+    }
+}
@@ -0,0 +1,151 @@
+/// A read-only snapshot of the `Pool` for the `ScalingPolicy`, see `Pool::state()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolState {
+    pub min: usize,
+    /// `Builder::max` plus the extra threads allowed for the running `Pool::push_blocking()` `Task`s.
+    pub max: usize,
+    pub load_limit: usize,
+    pub threads_future: usize,
+    pub threads_alive: usize,
+    pub threads_waiting: usize,
+    pub threads_blocking: usize,
+    pub tasks_len: usize,
+    /// The p90 time the recent `Task`s waited in the queue, `None` if no `Task` started in the last second,
+    ///
+    /// only measured for the `ScalingPolicy` that `uses_queue_latency()`, always `None` otherwise.
+    pub queue_latency: Option<Duration>,
+    /// The number of `Task`s just pushed, `0` except in `ScalingPolicy::on_push()`.
+    pub pushed: usize,
+}
+
+/// The decision of the `ScalingPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scaling {
+    #[default]
+    Keep,
+    /// Spawns the threads, up to `PoolState::max`.
+    Spawn(usize),
+    /// Retires the idle threads, down to `PoolState::min`.
+    ///
+    /// From `ScalingPolicy::on_idle_timeout()` it retires the timed out thread itself.
+    Retire(usize),
+}
+
+/// Decides when the `Pool` spawns or retires threads, see `Builder::scaling_policy()`.
+pub trait ScalingPolicy: Debug + Send + Sync {
+    /// Called after `Task`s were pushed.
+    fn on_push(&self, state: &PoolState) -> Scaling;
+    /// Called when a worker waited `Builder::timeout` without a `Task`,
+    ///
+    /// default retires it if the `Pool` has more threads than `min`: the threads above `min` keep alive for `timeout`.
+    fn on_idle_timeout(&self, state: &PoolState) -> Scaling {
+        if state.threads_future > state.min {
+            Scaling::Retire(1)
+        } else {
+            Scaling::Keep
+        }
+    }
+    /// Called every `Builder::daemon` by the daemon thread, default tops the threads up to `min`.
+    fn on_tick(&self, state: &PoolState) -> Scaling {
+        if state.threads_future < state.min {
+            Scaling::Spawn(state.min - state.threads_future)
+        } else {
+            Scaling::Keep
+        }
+    }
+    /// Whether it reads `PoolState::queue_latency`, default `false`: measuring it costs every `Task` a clock read and a lock.
+    fn uses_queue_latency(&self) -> bool {
+        false
+    }
+}
+
+/// The default `ScalingPolicy`: keeps `tasks_len / threads` under `Builder::load_limit`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoadLimit;
+
+impl ScalingPolicy for LoadLimit {
+    // 等待中的线程先消化, 其余按 tasks_len / threads <= load_limit 补足。
+    fn on_push(&self, state: &PoolState) -> Scaling {
+        let len = state.threads_future;
+        let excess = state.pushed.saturating_sub(state.threads_waiting);
+        if state.pushed == 0 || len != 0 && (len >= state.max || excess == 0) {
+            return Scaling::Keep;
+        }
+        // 运行阻塞任务的线程不计入。
+        let want = state.tasks_len / state.load_limit.saturating_add(1) + 1 + state.threads_blocking;
+        let want = cmp::min(state.max, want);
+        let add = cmp::min(want.saturating_sub(len), excess);
+        match if len == 0 { cmp::max(add, 1) } else { add } {
+            0 => Scaling::Keep,
+            add => Scaling::Spawn(add),
+        }
+    }
+}
+
+/// Spawns a thread for every `Task` no waiting thread can take at once.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Eager;
+
+impl ScalingPolicy for Eager {
+    fn on_push(&self, state: &PoolState) -> Scaling {
+        match state.pushed.saturating_sub(state.threads_waiting) {
+            0 => Scaling::Keep,
+            add => Scaling::Spawn(add),
+        }
+    }
+}
+
+/// Keeps `min` threads, only the `Pool::push_blocking()` `Task`s get extra threads.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedSize;
+
+impl ScalingPolicy for FixedSize {
+    fn on_push(&self, state: &PoolState) -> Scaling {
+        let want = cmp::max(state.min, 1) + state.threads_blocking;
+        match want.saturating_sub(state.threads_future) {
+            0 => Scaling::Keep,
+            add => Scaling::Spawn(add),
+        }
+    }
+}
+
+impl Inner {
+    fn state(&self, pushed: usize) -> PoolState {
+        PoolState {
+            min: self.as_builder().min_get(),
+            max: self.max_effective(),
+            load_limit: self.as_builder().load_limit_get(),
+            threads_future: self.threads_future(),
+            threads_alive: self.threads_alive(),
+            threads_waiting: self.threads_waiting(),
+            threads_blocking: self.threads_blocking(),
+            tasks_len: self.tasks_len(),
+            queue_latency: self.as_builder().latency.p90(),
+            pushed,
+        }
+    }
+    // 新建线程失败时返回 false。
+    fn apply(&self, scaling: Scaling) -> bool {
+        match scaling {
+            Scaling::Keep => true,
+            Scaling::Spawn(add) => {
+                let len = self.threads_future();
+                let add = cmp::min(add, self.max_effective().saturating_sub(len));
+                // 无线程时至少新建一个, 否则任务会一直阻塞。
+                let add = if len == 0 { cmp::max(add, 1) } else { add };
+                add == 0 || self.grow(add).is_ok()
+            }
+            Scaling::Retire(num) => {
+                self.remove_threads(num);
+                true
+            }
+        }
+    }
+}
+
+impl Pool {
+    /// Returns a snapshot of the Pool's threads and `Task`s.
+    pub fn state(&self) -> PoolState {
+        self.inner.state(0)
+    }
+}
@@ -0,0 +1,124 @@
+/// The handle of a scheduled task, returned by `Pool::schedule_fixed_rate()`, `Pool::schedule_fixed_delay()` and `Pool::schedule_cron()`.
+#[derive(Debug, Clone)]
+pub struct ScheduledHandle {
+    stopped: Arc<AtomicBool>,
+}
+
+impl ScheduledHandle {
+    fn new() -> Self {
+        ScheduledHandle { stopped: Arc::default() }
+    }
+    /// Stops the scheduled task, the running one will not be interrupted.
+    ///
+    /// Returns `false` if it has already been stopped.
+    pub fn stop(&self) -> bool {
+        !self.stopped.swap(true, Ordering::SeqCst)
+    }
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+}
+
+// 周期性任务。
+struct Periodic {
+    f: Box<dyn Fn() + Send + Sync + 'static>,
+    running: AtomicBool,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Periodic {
+    fn new<F>(f: F) -> (Arc<Self>, ScheduledHandle)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let handle = ScheduledHandle::new();
+        let periodic = Periodic {
+            f: Box::new(f),
+            running: AtomicBool::default(),
+            stopped: handle.stopped.clone(),
+        };
+        (Arc::new(periodic), handle)
+    }
+    #[inline]
+    fn stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+    // 上次执行未结束则跳过本次。
+    fn run(periodic: Arc<Self>, inner: &Inner) {
+        if periodic.running.swap(true, Ordering::SeqCst) {
+            debug!("skip the scheduled task: it is still running");
+            return;
+        }
+        inner.push(
+            Box::new(move || {
+                let _running = Alive::add(&periodic.running);
+                (periodic.f)();
+            }),
+            0,
+        );
+    }
+    // 按固定频率执行。
+    fn fixed_rate(periodic: Arc<Self>, period: Duration, at: Instant) -> Fire {
+        Box::new(move |inner: &Inner| {
+            if periodic.stopped() {
+                return;
+            }
+            let now = Instant::now();
+            let mut next = at + period;
+            while next <= now {
+                next += period;
+            }
+            inner.schedule(next, periodic.stopped.clone(), Self::fixed_rate(periodic.clone(), period, next));
+            Self::run(periodic, inner);
+        })
+    }
+    // 上次执行结束后再等待固定的时间。
+    fn fixed_delay(periodic: Arc<Self>, delay: Duration) -> Fire {
+        Box::new(move |inner: &Inner| {
+            if periodic.stopped() {
+                return;
+            }
+            let pool = inner.downgrade();
+            inner.push(
+                Box::new(move || {
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| (periodic.f)()));
+                    if let Some(inner) = Inner::upgrade(&pool) {
+                        let at = Instant::now() + delay;
+                        inner.schedule(at, periodic.stopped.clone(), Self::fixed_delay(periodic.clone(), delay));
+                    }
+                    if let Err(e) = result {
+                        panic::resume_unwind(e);
+                    }
+                }),
+                0,
+            );
+        })
+    }
+}
+
+impl Pool {
+    /// Runs `f` on the Pool after `initial_delay`, then at a fixed rate of `period`,
+    ///
+    /// the execution is skipped if the previous one is still running.
+    pub fn schedule_fixed_rate<F>(&self, initial_delay: Duration, period: Duration, f: F) -> ScheduledHandle
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        assert!(period > Duration::from_millis(0), "period == 0");
+        let (periodic, handle) = Periodic::new(f);
+        let at = Instant::now() + initial_delay;
+        self.inner.schedule(at, handle.stopped.clone(), Periodic::fixed_rate(periodic, period, at));
+        handle
+    }
+    /// Runs `f` on the Pool after `initial_delay`, then waits `delay` after each execution finished.
+    pub fn schedule_fixed_delay<F>(&self, initial_delay: Duration, delay: Duration, f: F) -> ScheduledHandle
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let (periodic, handle) = Periodic::new(f);
+        let at = Instant::now() + initial_delay;
+        self.inner.schedule(at, handle.stopped.clone(), Periodic::fixed_delay(periodic, delay));
+        handle
+    }
+}
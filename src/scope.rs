@@ -3,7 +3,7 @@ impl Pool {
     where
         Scheduler: FnOnce(&Scoped<'pool, 'scope>),
     {
-        let scoped = Scoped::new(&self);
+        let scoped = Scoped::new(self);
         scheduler(&scoped);
     }
 }
@@ -19,7 +19,7 @@ pub struct Scoped<'pool, 'scope> {
 impl<'pool, 'scope> Scoped<'pool, 'scope> {
     fn new(pool: &'pool Pool) -> Self {
         Self {
-            pool: pool,
+            pool,
             maker: PhantomData,
             tasks: Arc::default(),
         }
@@ -28,7 +28,7 @@ impl<'pool, 'scope> Scoped<'pool, 'scope> {
     where
         T: Runable + Send + 'scope,
     {
-        let task = unsafe { transmute::<Box<Runable + Send + 'scope>, Box<Runable + Send + 'static>>(Box::new(task)) };
+        let task = unsafe { transmute::<Box<dyn Runable + Send + 'scope>, Box<dyn Runable + Send + 'static>>(Box::new(task)) };
         let arc = self.tasks.clone();
 
         let task = move || {
@@ -0,0 +1,50 @@
+const SPAWN_RETRIES: u32 = 3;
+const SPAWN_BACKOFF_MS: u64 = 100;
+
+type OnSpawnError = Box<dyn Fn(&io::Error, u32) + Send + Sync>;
+
+impl Inner {
+    // 新建线程失败后, 按 backoff * 2^attempt 由定时器重试, 同时只有一个重试在等待。
+    fn spawn_failed(&self, e: &io::Error, attempt: Option<u32>, num: usize) {
+        if let Some(f) = self.as_builder().on_spawn_error.as_ref() {
+            f(e, attempt.unwrap_or(0));
+        }
+        let (retries, backoff) = self.as_builder().spawn_retry_get();
+        let attempt = match attempt {
+            Some(attempt) if attempt < retries && !self.dropped() => attempt,
+            _ => return,
+        };
+        if self.as_builder().spawn_retrying.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let delay = backoff * 2u32.saturating_pow(attempt);
+        self.schedule(
+            Instant::now() + delay,
+            Arc::default(),
+            Box::new(move |inner: &Inner| {
+                inner.as_builder().spawn_retrying.store(false, Ordering::SeqCst);
+                // 只补足 ScalingPolicy 仍然允许的部分。
+                let num = cmp::min(num, inner.max_effective().saturating_sub(inner.threads_future()));
+                if num > 0 && !inner.dropped() {
+                    let _ = inner.spawn_threads(num, Some(attempt + 1));
+                }
+            }),
+        );
+    }
+    // 新建线程失败且没有线程时, 在调用者线程上执行它刚添加的任务,
+    // daemon 和定时器线程上不执行, 任务留在队列里等待重试。
+    fn run_on_caller(&self, seqs: Range<u64>) {
+        // 其它线程正在新建(可能也会失败)时 threads_future 不为零, 线程正在退出时 threads_alive 不为零。
+        let idle = self.threads_future() == 0 || self.threads_alive() == 0;
+        if !idle || self.dropped() || is_timer_thread() {
+            return;
+        }
+        for job in self.as_builder().queue.remove(seqs) {
+            debug!("no thread in the Pool, run the Task on the caller thread");
+            self.permit(&job);
+            if !self.expire(&job, Instant::now()) {
+                job.task.call();
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+/// How the workers take the `Task`s, see `Builder::scheduler()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scheduler {
+    /// All workers take `Task`s from the shared priority queue, the default.
+    #[default]
+    Shared,
+    /// Each worker has a local deque: the `Task`s pushed from a worker go to its local deque,
+    /// the idle workers steal from others, and the `Task`s pushed from outside go through the shared queue.
+    ///
+    /// The `Priority` and aging only apply to the shared queue.
+    WorkStealing,
+}
+
+// 工作线程的本地队列。
+type Local = Mutex<VecDeque<Job>>;
+
+thread_local! {
+    // 当前工作线程所属的 Pool 及其本地队列。
+    static LOCAL: RefCell<Option<(usize, Arc<Local>)>> = const { RefCell::new(None) };
+    // 当前工作线程所属的 Pool, 与调度方式无关。
+    static WORKER: Cell<usize> = const { Cell::new(0) };
+}
+
+// The local deques of the workers, only used by `Scheduler::WorkStealing`.
+#[derive(Default)]
+struct Locals {
+    deques: RwLock<Vec<(usize, Arc<Local>)>>,
+}
+
+impl Locals {
+    #[inline]
+    fn read(&self) -> RwLockReadGuard<'_, Vec<(usize, Arc<Local>)>> {
+        self.deques.read().unwrap_or_else(|e| e.into_inner())
+    }
+    #[inline]
+    fn write(&self) -> RwLockWriteGuard<'_, Vec<(usize, Arc<Local>)>> {
+        self.deques.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Debug for Locals {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_list().entries(self.read().iter().map(|l| l.1.lock().map(|d| d.len()).unwrap_or(0))).finish()
+    }
+}
+
+// 通过作用域注册本地队列, 退出时把剩余的任务移到共享队列。
+struct LocalGuard<'a> {
+    inner: &'a Inner,
+    id: usize,
+    local: Arc<Local>,
+}
+
+impl<'a> Drop for LocalGuard<'a> {
+    fn drop(&mut self) {
+        LOCAL.with(|l| *l.borrow_mut() = None);
+        self.inner.as_builder().locals.write().retain(|l| l.0 != self.id);
+        let jobs = mem::take(&mut *self.local.lock().unwrap_or_else(|e| e.into_inner()));
+        for job in jobs {
+            self.inner.as_builder().queue.locals.fetch_sub(1, Ordering::SeqCst);
+            self.inner.as_builder().queue.push(job);
+        }
+    }
+}
+
+impl Inner {
+    #[inline]
+    fn id(&self) -> usize {
+        &*self.workers as *const Builder as usize
+    }
+    #[inline]
+    fn is_worker(&self) -> bool {
+        WORKER.with(|w| w.get()) == self.id()
+    }
+    fn register_local(&self, id: usize) -> LocalGuard<'_> {
+        let local = Arc::new(Local::default());
+        self.as_builder().locals.write().push((id, local.clone()));
+        LOCAL.with(|l| *l.borrow_mut() = Some((self.id(), local.clone())));
+        LocalGuard { inner: self, id, local }
+    }
+    // 在本 Pool 的工作线程里添加的任务进入其本地队列。
+    fn push_local(&self, job: Job) -> Result<(), Job> {
+        // 命名队列的任务总是进入共享队列, 以保证公平。
+        if *self.as_builder().scheduler_get() != Scheduler::WorkStealing || job.lane != 0 {
+            return Err(job);
+        }
+        LOCAL.with(|l| match *l.borrow() {
+            Some((id, ref local)) if id == self.id() => {
+                let queue = &self.as_builder().queue;
+                // 先计数再入队: 任务被取走后才减一, locals 不会小于实际的任务数。
+                queue.locals.fetch_add(1, Ordering::SeqCst);
+                local.lock().unwrap_or_else(|e| e.into_inner()).push_back(job);
+                queue.push_local(&self.as_builder().threads_waiting);
+                Ok(())
+            }
+            _ => Err(job),
+        })
+    }
+    // 从本地队列取最后添加的任务, 不碰共享队列的锁。
+    fn take_local(&self, guard: &LocalGuard) -> Option<Job> {
+        self.take(&guard.local, true)
+    }
+    // 共享队列已空时, 从其它工作线程的本地队列偷最早添加的任务。
+    fn steal(&self, guard: &LocalGuard) -> Option<Job> {
+        let locals = self.as_builder().locals.read();
+        let start = locals.iter().position(|l| l.0 == guard.id).unwrap_or(0);
+        locals.iter().cycle().skip(start + 1).take(locals.len()).filter(|l| l.0 != guard.id).find_map(|l| self.take(&l.1, false))
+    }
+    fn take(&self, local: &Local, back: bool) -> Option<Job> {
+        let mut local = local.lock().unwrap_or_else(|e| e.into_inner());
+        let job = if back { local.pop_back() } else { local.pop_front() };
+        if job.is_some() {
+            self.as_builder().queue.locals.fetch_sub(1, Ordering::SeqCst);
+        }
+        job
+    }
+}
@@ -0,0 +1,222 @@
+/// Runs on the daemon(or timer) thread when a timer fires.
+type Fire = Box<dyn FnOnce(&Inner) + Send + 'static>;
+
+thread_local! {
+    // 当前线程是 daemon 或定时器线程。
+    static TIMER_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+#[inline]
+fn is_timer_thread() -> bool {
+    TIMER_THREAD.with(|t| t.get())
+}
+
+/// The handle of a pending timer, returned by `Pool::push_after()` and `Pool::push_at()`.
+#[derive(Debug, Clone)]
+pub struct TimerHandle {
+    done: Arc<AtomicBool>,
+    pool: Weak<Builder>,
+}
+
+impl TimerHandle {
+    fn new(pool: Weak<Builder>) -> Self {
+        TimerHandle { done: Arc::default(), pool }
+    }
+    /// Cancels the pending timer, returns `false` if it has already fired or been cancelled.
+    ///
+    /// The `Task` is dropped soon after, not kept until the time it was due.
+    pub fn cancel(&self) -> bool {
+        if self.done.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        if let Some(inner) = Inner::upgrade(&self.pool) {
+            inner.as_builder().timers.cancelled();
+        }
+        true
+    }
+    /// The timer has fired or been cancelled.
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::SeqCst)
+    }
+}
+
+struct Timer {
+    at: Instant,
+    seq: u64,
+    done: Arc<AtomicBool>,
+    fire: Fire,
+}
+
+// BinaryHeap 是大顶堆, 反转顺序让最早的定时器在堆顶。
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (other.at, other.seq).cmp(&(self.at, self.seq))
+    }
+}
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.at == other.at && self.seq == other.seq
+    }
+}
+impl Eq for Timer {}
+
+// The timers, serviced by the daemon thread, or the timer thread if the daemon is not alive.
+struct Timers {
+    heap: Mutex<TimerHeap>,
+    cond: Condvar,
+    thread_alive: AtomicBool,
+}
+
+#[derive(Default)]
+struct TimerHeap {
+    timers: BinaryHeap<Timer>,
+    // 堆中已取消但未到期的定时器数(估计值)。
+    cancelled: usize,
+    seq: u64,
+    closed: bool,
+}
+
+impl Timers {
+    fn new() -> Self {
+        Timers {
+            heap: Mutex::default(),
+            cond: Condvar::new(),
+            thread_alive: AtomicBool::default(),
+        }
+    }
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, TimerHeap> {
+        self.heap.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    fn push(&self, at: Instant, done: Arc<AtomicBool>, fire: Fire) {
+        {
+            let mut heap = self.lock();
+            heap.seq += 1;
+            let seq = heap.seq;
+            heap.timers.push(Timer { at, seq, done, fire });
+        }
+        self.cond.notify_all();
+    }
+    #[inline]
+    fn len(&self) -> usize {
+        self.lock().timers.len()
+    }
+    // 已取消的超过一半时重建堆, 及早释放它们的闭包, 均摊到每次取消是 O(1)。
+    fn cancelled(&self) {
+        let removed = {
+            let mut heap = self.lock();
+            heap.cancelled += 1;
+            if heap.cancelled * 2 <= heap.timers.len() {
+                return;
+            }
+            heap.cancelled = 0;
+            let (removed, kept): (Vec<_>, _) = mem::take(&mut heap.timers)
+                .into_vec()
+                .into_iter()
+                .partition(|t| t.done.load(Ordering::SeqCst));
+            heap.timers = BinaryHeap::from(kept);
+            removed
+        };
+        // 解锁后释放, 闭包的 drop 可能会添加新的定时器。
+        drop(removed);
+    }
+    /// Fires the due timers until `until` or the `Timers` closed.
+    fn service(&self, inner: &Inner, until: Instant) {
+        let mut heap = self.lock();
+        loop {
+            if heap.closed {
+                return;
+            }
+            let now = Instant::now();
+            let mut due = Vec::new();
+            while heap.timers.peek().map(|t| t.at <= now).unwrap_or(false) {
+                let timer = heap.timers.pop().unwrap();
+                if !timer.done.load(Ordering::SeqCst) {
+                    due.push(timer.fire);
+                } else {
+                    heap.cancelled = heap.cancelled.saturating_sub(1);
+                }
+            }
+            if !due.is_empty() {
+                // 解锁后执行, fire 可能会添加新的定时器。
+                drop(heap);
+                for fire in due {
+                    // 一个 fire panic 不影响同一批的其它定时器。
+                    if panic::catch_unwind(AssertUnwindSafe(|| fire(inner))).is_err() {
+                        error!("a timer panicked");
+                    }
+                }
+                heap = self.lock();
+                continue;
+            }
+            if now >= until {
+                return;
+            }
+            let next = heap.timers.peek().map(|t| cmp::min(t.at, until)).unwrap_or(until);
+            heap = self.cond.wait_timeout(heap, next - now).unwrap_or_else(|e| e.into_inner()).0;
+        }
+    }
+    fn close(&self) {
+        self.lock().closed = true;
+        self.cond.notify_all();
+    }
+}
+
+impl Debug for Timers {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Timers")
+            .field("len", &self.len())
+            .field("thread_alive", &self.thread_alive)
+            .finish()
+    }
+}
+
+impl Inner {
+    pub fn push_at(&self, at: Instant, task: Task) -> TimerHandle {
+        let handle = TimerHandle::new(self.downgrade());
+        let done = handle.done.clone();
+        self.schedule(
+            at,
+            handle.done.clone(),
+            Box::new(move |inner: &Inner| if !done.swap(true, Ordering::SeqCst) {
+                inner.push(task, 0)
+            }),
+        );
+        handle
+    }
+    fn schedule(&self, at: Instant, done: Arc<AtomicBool>, fire: Fire) {
+        self.as_builder().timers.push(at, done, fire);
+        if !self.daemon_alive() {
+            let _ = self.run_timer();
+        }
+    }
+    // 没有 daemon 时由专门的线程处理定时器。
+    fn run_timer(&self) -> io::Result<()> {
+        let timers = &self.as_builder().timers;
+        if timers.thread_alive.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let timer = self.clone();
+        let mut b = thread::Builder::new();
+        if let Some(name) = timer.as_builder().name_get() {
+            b = b.name(name.to_string());
+        }
+        b.spawn(move || {
+            let timer = timer;
+            let _alive = Alive::add(&timer.as_builder().timers.thread_alive);
+            TIMER_THREAD.with(|t| t.set(true));
+            while !timer.dropped() {
+                timer.as_builder().timers.service(&timer, Instant::now() + Duration::from_millis(TIME_OUT_MS));
+            }
+        }).map(|_| ())
+            .log_err(|e| {
+                timers.thread_alive.store(false, Ordering::SeqCst);
+                error!("spawn timer thread failed: '{}' !", e)
+            })
+    }
+}
@@ -0,0 +1,132 @@
+/// What `Pool::push_unique()` does if a `Task` with the same key is still queued, see `Builder::coalesce()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Coalesce {
+    /// Drops the new `Task`, the default.
+    #[default]
+    KeepQueued,
+    /// The new `Task` replaces the queued one, and takes its place in the queue.
+    Replace,
+}
+
+// 排队中(尚未开始)的唯一任务。
+type Slot = Arc<Mutex<Option<Task>>>;
+
+// 一种 key 类型的 HashMap<K, V>, 见 KeyMaps。
+trait KeyMap: Send {
+    fn len(&self) -> usize;
+    fn as_any(&mut self) -> &mut dyn Any;
+}
+
+impl<K, V> KeyMap for HashMap<K, V>
+where
+    K: Eq + Hash + Send + 'static,
+    V: Send + 'static,
+{
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// 按类型分开存放的 HashMap<K, V>, 保留 key 本身并按相等比较, 哈希值相同的不同 key 互不影响。
+#[derive(Default)]
+struct KeyMaps {
+    maps: HashMap<TypeId, Box<dyn KeyMap>>,
+}
+
+impl KeyMaps {
+    fn get<K, V>(&mut self) -> &mut HashMap<K, V>
+    where
+        K: Eq + Hash + Send + 'static,
+        V: Send + 'static,
+    {
+        self.maps
+            .entry(TypeId::of::<HashMap<K, V>>())
+            .or_insert_with(|| Box::new(HashMap::<K, V>::new()))
+            .as_any()
+            .downcast_mut()
+            .unwrap()
+    }
+    fn len(&self) -> usize {
+        self.maps.values().map(|m| m.len()).sum()
+    }
+}
+
+// The queued `Task`s of `Pool::push_unique()`.
+#[derive(Default)]
+struct Uniques {
+    keys: Mutex<KeyMaps>,
+}
+
+impl Uniques {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, KeyMaps> {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Debug for Uniques {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Uniques").field("len", &self.lock().len()).finish()
+    }
+}
+
+impl Inner {
+    #[inline]
+    pub fn tasks_coalesced(&self) -> usize {
+        self.as_builder().tasks_coalesced.load(Ordering::Relaxed)
+    }
+    fn push_unique<K>(&self, key: K, task: Task)
+    where
+        K: Eq + Hash + Clone + Send + 'static,
+    {
+        let slot = {
+            let mut uniques = self.as_builder().uniques.lock();
+            let keys = uniques.get::<K, Slot>();
+            if let Some(slot) = keys.get(&key) {
+                self.as_builder().tasks_coalesced.fetch_add(1, Ordering::Relaxed);
+                if *self.as_builder().coalesce_get() == Coalesce::Replace {
+                    *slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(task);
+                }
+                return;
+            }
+            let slot = Slot::new(Mutex::new(Some(task)));
+            keys.insert(key.clone(), slot.clone());
+            slot
+        };
+        let pool = self.downgrade();
+        self.push(
+            Box::new(move || {
+                // 开始执行后, 同一 key 的任务可以再次入队。
+                if let Some(inner) = Inner::upgrade(&pool) {
+                    inner.as_builder().uniques.lock().get::<K, Slot>().remove(&key);
+                }
+                let task = slot.lock().unwrap_or_else(|e| e.into_inner()).take();
+                if let Some(task) = task {
+                    task.call();
+                }
+            }),
+            0,
+        )
+    }
+}
+
+impl Pool {
+    /// Appends a task to the Pool with the key,
+    ///
+    /// if a `Task` with an equal key is still queued(not started), the new one is coalesced by `Builder::coalesce()`
+    /// and counted in `tasks_coalesced()`.
+    pub fn push_unique<K, T>(&self, key: K, task: T)
+    where
+        K: Eq + Hash + Clone + Send + 'static,
+        T: Runable + Send + 'static,
+    {
+        self.inner.push_unique(key, Box::new(task))
+    }
+    /// Returns the number of `Task`s coalesced by `Pool::push_unique()`.
+    pub fn tasks_coalesced(&self) -> usize {
+        self.inner.tasks_coalesced()
+    }
+}
@@ -0,0 +1,97 @@
+/// The minimum interval(ms) the watchdog checks the running `Task`s.
+const WATCHDOG_MS: u64 = 10;
+
+/// The callback for the stuck `Task`: the worker's thread name, the `Task`'s name and how long it has been running.
+type OnTaskWarn = Box<dyn Fn(&str, Option<&str>, Duration) + Send + Sync + 'static>;
+
+// 正在执行的任务。
+struct Busy {
+    thread: String,
+    task: Option<String>,
+    since: Instant,
+    warned: bool,
+}
+
+// The running `Task`s, tracked only if `Builder::task_warn_after()` is set.
+#[derive(Default)]
+struct Watchdog {
+    busy: Mutex<BTreeMap<usize, Busy>>,
+    ids: AtomicUsize,
+}
+
+// 通过作用域记录任务的执行。
+struct BusyGuard<'a> {
+    watchdog: &'a Watchdog,
+    id: usize,
+}
+
+impl<'a> Drop for BusyGuard<'a> {
+    fn drop(&mut self) {
+        self.watchdog.lock().remove(&self.id);
+    }
+}
+
+impl Watchdog {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<usize, Busy>> {
+        self.busy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    // 工作线程的编号。
+    #[inline]
+    fn id(&self) -> usize {
+        self.ids.fetch_add(1, Ordering::Relaxed)
+    }
+    fn busy(&self, id: usize, task: Option<&String>) -> BusyGuard<'_> {
+        let current = thread::current();
+        let thread = match current.name() {
+            Some(name) => name.to_owned(),
+            None => format!("{:?}", current.id()),
+        };
+        let busy = Busy {
+            thread,
+            task: task.cloned(),
+            since: Instant::now(),
+            warned: false,
+        };
+        self.lock().insert(id, busy);
+        BusyGuard { watchdog: self, id }
+    }
+    // 每个任务只报告一次。
+    fn check(&self, warn_after: Duration) -> Vec<(String, Option<String>, Duration)> {
+        let now = Instant::now();
+        self.lock()
+            .values_mut()
+            .filter(|b| !b.warned && now.duration_since(b.since) >= warn_after)
+            .map(|b| {
+                b.warned = true;
+                (b.thread.clone(), b.task.clone(), now.duration_since(b.since))
+            })
+            .collect()
+    }
+    fn len(&self) -> usize {
+        self.lock().len()
+    }
+}
+
+impl Debug for Watchdog {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Watchdog").field("busy", &self.len()).finish()
+    }
+}
+
+impl Inner {
+    // 由 daemon(或定时器)线程周期性地检查。
+    fn watchdog(warn_after: Duration) -> Fire {
+        Box::new(move |inner: &Inner| {
+            // 先安排下一次检查, 回调 panic 也不会停止 watchdog。
+            let interval = cmp::max(warn_after / 2, Duration::from_millis(WATCHDOG_MS));
+            inner.schedule(Instant::now() + interval, Arc::default(), Self::watchdog(warn_after));
+            for (thread, task, elapsed) in inner.as_builder().watchdog.check(warn_after) {
+                match inner.as_builder().on_task_warn.as_ref() {
+                    Some(f) => f(&thread, task.as_deref(), elapsed),
+                    None => warn!("Task {:?} has been running on thread '{}' for {:?}", task, thread, elapsed),
+                }
+            }
+        })
+    }
+}
@@ -49,7 +49,7 @@ fn fibm() {
         pool.threads_future(),
     );
     thread::sleep(Duration::from_millis(5000));
-    eprint!("loop0 finished ! main slept 5000 ms !\n");
+    eprintln!("loop0 finished ! main slept 5000 ms !");
 
     eprint!(
         "\nis_empty()/tasks_len(): {}/{} -> waiting()/alive()/future(): {}/{}/{}\n",
@@ -70,7 +70,7 @@ fn fibm() {
         count += 1;
     }
     thread::sleep(Duration::from_millis(6000));
-    eprint!("loop1 finished ! main slept 6000 ms !\n");
+    eprintln!("loop1 finished ! main slept 6000 ms !");
     eprint!(
         "\nis_empty()/tasks_len(): {}/{} -> waiting()/alive()/future(): {}/{}/{}\n",
         pool.is_empty(),
@@ -98,7 +98,7 @@ fn fibm() {
     }
     fn fib(msg: i32) -> i32 {
         match msg {
-            0...2 => 1,
+            0..=2 => 1,
             x => fib(x - 1) + fib(x - 2),
         }
     }